@@ -0,0 +1,34 @@
+//! JSON files kept in the app data dir by the Rust-side stores.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+/// Reads a store file, treating a missing or unreadable file as empty so a
+/// corrupt file never blocks startup.
+pub fn read_json_file<T: DeserializeOwned + Default>(path: &Path) -> T {
+  match fs::read_to_string(path) {
+    Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|error| {
+      log::warn!("Ignoring unreadable store file {}: {error}", path.display());
+      T::default()
+    }),
+    Err(_) => T::default(),
+  }
+}
+
+/// Writes a store file, creating the app data dir on first use. Failures are
+/// logged rather than returned: the in-memory state stays authoritative.
+pub fn write_json_file<T: Serialize + ?Sized>(path: &Path, value: &T) {
+  let result = path
+    .parent()
+    .map(fs::create_dir_all)
+    .transpose()
+    .and_then(|_| {
+      let contents = serde_json::to_string_pretty(value).map_err(std::io::Error::other)?;
+      fs::write(path, contents)
+    });
+  if let Err(error) = result {
+    log::warn!("Failed to write store file {}: {error}", path.display());
+  }
+}
@@ -0,0 +1,295 @@
+//! Guarded container lifecycle actions.
+//!
+//! Actions run in two steps: `prepare_container_action` describes the exact
+//! remote command and returns a short-lived, single-use confirmation token,
+//! and `run_container_action` executes it only when given that token. Every
+//! run, successful or not, is appended to `container_actions.json` in the app
+//! data dir.
+
+use crate::app_data::{read_json_file, write_json_file};
+use crate::container_logs::valid_container_name;
+use crate::error::{CommandError, CommandResult};
+use crate::now_millis;
+use crate::server_profiles::{open_session, ServerProfileStore};
+use crate::ssh::{shell_escape, shell_escape_path, RemoteOutput, RemoteShell};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+
+pub const CONTAINER_ACTIONS_FILE: &str = "container_actions.json";
+/// How long a confirmation token stays valid.
+const CONFIRMATION_TTL_MS: u64 = 2 * 60 * 1000;
+/// Oldest audit entries are dropped beyond this many.
+const MAX_AUDIT_ENTRIES: usize = 1_000;
+const DEFAULT_AUDIT_LIMIT: usize = 100;
+const CONTAINER_COMMAND_TIMEOUT: Duration = Duration::from_secs(3 * 60);
+/// Image pulls can take a while on slow links.
+const COMPOSE_COMMAND_TIMEOUT: Duration = Duration::from_secs(20 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContainerAction {
+  Restart,
+  Stop,
+  Start,
+  /// `docker compose pull && docker compose up -d` in the profile's project dir.
+  ComposeUpdate,
+}
+
+/// A prepared action awaiting confirmation.
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingContainerAction {
+  pub token: String,
+  pub profile_id: String,
+  pub profile_name: String,
+  pub host: String,
+  pub action: ContainerAction,
+  /// Container name, or the compose project directory.
+  pub target: String,
+  /// The exact remote command that will run.
+  pub command: String,
+  pub expires_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContainerActionResult {
+  pub action: ContainerAction,
+  pub target: String,
+  pub ok: bool,
+  pub exit_status: i32,
+  pub stdout: String,
+  pub stderr: String,
+  pub started_at: u64,
+  pub finished_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContainerActionAuditEntry {
+  pub profile_id: String,
+  pub profile_name: String,
+  pub host: String,
+  pub action: ContainerAction,
+  pub target: String,
+  pub command: String,
+  pub started_at: u64,
+  pub finished_at: u64,
+  pub ok: bool,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub exit_status: Option<i32>,
+  /// Set when the command could not be run at all.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+}
+
+pub struct ContainerActionStore {
+  path: PathBuf,
+  audit: Mutex<Vec<ContainerActionAuditEntry>>,
+  pending: Mutex<HashMap<String, PendingContainerAction>>,
+}
+
+impl ContainerActionStore {
+  pub fn load(path: PathBuf) -> Self {
+    let audit = read_json_file(&path);
+    Self {
+      path,
+      audit: Mutex::new(audit),
+      pending: Mutex::new(HashMap::new()),
+    }
+  }
+
+  fn audit(&self) -> MutexGuard<'_, Vec<ContainerActionAuditEntry>> {
+    self.audit.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+
+  fn pending(&self) -> MutexGuard<'_, HashMap<String, PendingContainerAction>> {
+    self.pending.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+
+  fn hold(&self, action: PendingContainerAction) {
+    let now = now_millis();
+    let mut pending = self.pending();
+    pending.retain(|_, held| held.expires_at > now);
+    pending.insert(action.token.clone(), action);
+  }
+
+  /// Consumes a confirmation token. A token is valid once, and only until it expires.
+  fn redeem(&self, token: &str) -> CommandResult<PendingContainerAction> {
+    let action = self.pending().remove(token).ok_or_else(|| {
+      CommandError::invalid_input("Unknown or already used confirmation token. Prepare the action again.")
+    })?;
+    if action.expires_at <= now_millis() {
+      return Err(CommandError::invalid_input(
+        "Confirmation token expired. Prepare the action again.",
+      ));
+    }
+    Ok(action)
+  }
+
+  fn record(&self, entry: ContainerActionAuditEntry) {
+    let mut audit = self.audit();
+    audit.push(entry);
+    let overflow = audit.len().saturating_sub(MAX_AUDIT_ENTRIES);
+    audit.drain(..overflow);
+    write_json_file(&self.path, &*audit);
+  }
+
+  /// Newest first, optionally for one profile.
+  fn list(&self, profile_id: Option<&str>, limit: usize) -> Vec<ContainerActionAuditEntry> {
+    self
+      .audit()
+      .iter()
+      .rev()
+      .filter(|entry| profile_id.map_or(true, |profile_id| entry.profile_id == profile_id))
+      .take(limit)
+      .cloned()
+      .collect()
+  }
+}
+
+fn remote_command(action: ContainerAction, target: &str) -> String {
+  match action {
+    ContainerAction::Restart => format!("docker restart {}", shell_escape(target)),
+    ContainerAction::Stop => format!("docker stop {}", shell_escape(target)),
+    ContainerAction::Start => format!("docker start {}", shell_escape(target)),
+    ContainerAction::ComposeUpdate => format!(
+      "cd {} && docker compose pull && docker compose up -d",
+      shell_escape_path(target)
+    ),
+  }
+}
+
+/// Runs a confirmed action. Blocking.
+fn run_action(app: &AppHandle, action: &PendingContainerAction) -> CommandResult<RemoteOutput> {
+  let (_, session) = open_session(app, &action.profile_id)?;
+  session.set_command_timeout(match action.action {
+    ContainerAction::ComposeUpdate => COMPOSE_COMMAND_TIMEOUT,
+    _ => CONTAINER_COMMAND_TIMEOUT,
+  });
+  session.run(&action.command)
+}
+
+/// Describes an action without running it and returns the token that
+/// `run_container_action` requires. `container` is required for restart, stop
+/// and start; `compose_update` uses the profile's compose project directory.
+#[tauri::command]
+pub fn prepare_container_action(
+  app: AppHandle,
+  profile_id: String,
+  action: ContainerAction,
+  container: Option<String>,
+) -> CommandResult<PendingContainerAction> {
+  let profile = app.state::<ServerProfileStore>().get(&profile_id)?;
+  let target = match action {
+    ContainerAction::ComposeUpdate => profile.compose_project_dir.clone().ok_or_else(|| {
+      CommandError::invalid_input("This server profile has no compose project directory configured.")
+    })?,
+    _ => {
+      let container = container.map(|name| name.trim().to_string()).unwrap_or_default();
+      if !valid_container_name(&container) {
+        return Err(CommandError::invalid_input("A valid container name is required."));
+      }
+      container
+    }
+  };
+  let pending = PendingContainerAction {
+    token: uuid::Uuid::new_v4().to_string(),
+    command: remote_command(action, &target),
+    profile_id: profile.id,
+    profile_name: profile.name,
+    host: profile.host,
+    action,
+    target,
+    expires_at: now_millis() + CONFIRMATION_TTL_MS,
+  };
+  app.state::<ContainerActionStore>().hold(pending.clone());
+  Ok(pending)
+}
+
+/// Runs a prepared action. A non-zero exit is returned as a result with
+/// `ok: false` so the captured output reaches the caller.
+#[tauri::command]
+pub async fn run_container_action(app: AppHandle, token: String) -> CommandResult<ContainerActionResult> {
+  let action = app.state::<ContainerActionStore>().redeem(&token)?;
+  let started_at = now_millis();
+  let task_app = app.clone();
+  let task_action = action.clone();
+  let output = tauri::async_runtime::spawn_blocking(move || run_action(&task_app, &task_action))
+    .await
+    .map_err(|error| CommandError::internal(format!("Container action task failed: {error}")))
+    .and_then(|result| result);
+  let finished_at = now_millis();
+
+  let entry = ContainerActionAuditEntry {
+    profile_id: action.profile_id.clone(),
+    profile_name: action.profile_name.clone(),
+    host: action.host.clone(),
+    action: action.action,
+    target: action.target.clone(),
+    command: action.command.clone(),
+    started_at,
+    finished_at,
+    ok: matches!(&output, Ok(output) if output.exit_status == 0),
+    exit_status: output.as_ref().ok().map(|output| output.exit_status),
+    error: output.as_ref().err().map(|error| error.message.clone()),
+  };
+  app.state::<ContainerActionStore>().record(entry);
+
+  let output = output?;
+  Ok(ContainerActionResult {
+    action: action.action,
+    target: action.target,
+    ok: output.exit_status == 0,
+    exit_status: output.exit_status,
+    stdout: output.stdout,
+    stderr: output.stderr,
+    started_at,
+    finished_at,
+  })
+}
+
+/// Drops a prepared action without running it. Returns whether the token was pending.
+#[tauri::command]
+pub fn cancel_container_action(store: State<'_, ContainerActionStore>, token: String) -> bool {
+  store.pending().remove(&token).is_some()
+}
+
+#[tauri::command]
+pub fn list_container_action_audit(
+  store: State<'_, ContainerActionStore>,
+  profile_id: Option<String>,
+  limit: Option<usize>,
+) -> Vec<ContainerActionAuditEntry> {
+  store.list(profile_id.as_deref(), limit.unwrap_or(DEFAULT_AUDIT_LIMIT))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn container_commands_quote_the_name() {
+    assert_eq!(remote_command(ContainerAction::Restart, "synapse"), "docker restart 'synapse'");
+    assert_eq!(remote_command(ContainerAction::Stop, "a'b"), "docker stop 'a'\"'\"'b'");
+  }
+
+  #[test]
+  fn compose_update_expands_home_relative_dirs() {
+    let command = |dir| remote_command(ContainerAction::ComposeUpdate, dir);
+    assert_eq!(
+      command("~/matrix stack"),
+      "cd \"$HOME\"/'matrix stack' && docker compose pull && docker compose up -d"
+    );
+    assert_eq!(command("~"), "cd \"$HOME\" && docker compose pull && docker compose up -d");
+    assert_eq!(command("~/"), "cd \"$HOME\" && docker compose pull && docker compose up -d");
+    assert_eq!(
+      command("/srv/matrix"),
+      "cd '/srv/matrix' && docker compose pull && docker compose up -d"
+    );
+    // `~user` paths and a `~` elsewhere in the path stay literal.
+    assert_eq!(command("~bob/x"), "cd '~bob/x' && docker compose pull && docker compose up -d");
+    assert_eq!(command("/srv/~/x"), "cd '/srv/~/x' && docker compose pull && docker compose up -d");
+  }
+}
@@ -0,0 +1,277 @@
+//! Live container log tail over SSH.
+//!
+//! `stream_container_logs` runs `docker logs --follow` for a container of a
+//! saved server profile and forwards each line over a Tauri channel until the
+//! remote command ends or `stop_container_logs` is called with the returned
+//! stream id. Synapse lines can be filtered by log level.
+
+use crate::error::{CommandError, CommandResult};
+use crate::server_profiles::open_session;
+use crate::ssh::{shell_escape, RemoteStream, StreamRead};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Manager, State};
+
+const DEFAULT_TAIL_LINES: u32 = 200;
+const MAX_TAIL_LINES: u32 = 5_000;
+/// How long to wait before polling an idle stream again.
+const IDLE_POLL: Duration = Duration::from_millis(100);
+const READ_BUFFER_BYTES: usize = 16 * 1024;
+
+/// Synapse's Python log levels, lowest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+  Debug,
+  Info,
+  Warning,
+  Error,
+  Critical,
+}
+
+impl LogLevel {
+  /// Reads the level from a Synapse line such as
+  /// `2024-05-01 12:00:00,123 - synapse.access - 450 - INFO - GET-1 - ...`.
+  fn parse(line: &str) -> Option<Self> {
+    line.split(" - ").take(5).find_map(|field| match field.trim() {
+      "DEBUG" => Some(LogLevel::Debug),
+      "INFO" => Some(LogLevel::Info),
+      "WARNING" | "WARN" => Some(LogLevel::Warning),
+      "ERROR" => Some(LogLevel::Error),
+      "CRITICAL" | "FATAL" => Some(LogLevel::Critical),
+      _ => None,
+    })
+  }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LogStreamMessage {
+  Line {
+    line: String,
+    /// Level of the line, or of the entry it continues (e.g. a traceback).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    level: Option<LogLevel>,
+  },
+  /// The stream ended, either because the remote command exited or it was stopped.
+  Ended { stopped: bool },
+  Failed { error: CommandError },
+}
+
+/// Splits raw output into lines and applies the level filter. Lines without a
+/// level inherit the previous entry's level so multi-line entries stay whole.
+struct LineFilter {
+  min_level: Option<LogLevel>,
+  current_level: Option<LogLevel>,
+  pending: Vec<u8>,
+}
+
+impl LineFilter {
+  fn push(&mut self, chunk: &[u8], mut emit: impl FnMut(String, Option<LogLevel>)) {
+    self.pending.extend_from_slice(chunk);
+    while let Some(end) = self.pending.iter().position(|byte| *byte == b'\n') {
+      let raw: Vec<u8> = self.pending.drain(..=end).collect();
+      self.emit_line(&raw, &mut emit);
+    }
+  }
+
+  /// Emits a trailing line that never got its newline, e.g. the last output
+  /// of a container that crashed mid-write.
+  fn finish(&mut self, mut emit: impl FnMut(String, Option<LogLevel>)) {
+    if !self.pending.is_empty() {
+      let raw = std::mem::take(&mut self.pending);
+      self.emit_line(&raw, &mut emit);
+    }
+  }
+
+  fn emit_line(&mut self, raw: &[u8], emit: &mut impl FnMut(String, Option<LogLevel>)) {
+    let line = String::from_utf8_lossy(raw).trim_end_matches(['\r', '\n']).to_string();
+    if let Some(level) = LogLevel::parse(&line) {
+      self.current_level = Some(level);
+    }
+    let visible = match (self.min_level, self.current_level) {
+      (Some(min_level), Some(level)) => level >= min_level,
+      _ => true,
+    };
+    if visible {
+      emit(line, self.current_level);
+    }
+  }
+}
+
+#[derive(Default)]
+pub struct LogStreamStore {
+  streams: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+impl LogStreamStore {
+  fn lock(&self) -> MutexGuard<'_, HashMap<String, Arc<AtomicBool>>> {
+    self.streams.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+
+  fn register(&self, stream_id: &str) -> Arc<AtomicBool> {
+    let stopped = Arc::new(AtomicBool::new(false));
+    self.lock().insert(stream_id.to_string(), stopped.clone());
+    stopped
+  }
+
+  fn stop(&self, stream_id: &str) -> bool {
+    match self.lock().remove(stream_id) {
+      Some(stopped) => {
+        stopped.store(true, Ordering::SeqCst);
+        true
+      }
+      None => false,
+    }
+  }
+}
+
+/// Docker container names: letters, digits, `_`, `.` and `-`.
+pub fn valid_container_name(name: &str) -> bool {
+  !name.is_empty()
+    && name
+      .chars()
+      .all(|character| character.is_ascii_alphanumeric() || matches!(character, '_' | '.' | '-'))
+}
+
+/// Forwards output until the command ends, the frontend goes away or the
+/// stream is stopped. Runs on its own thread.
+fn pump(
+  mut stream: RemoteStream,
+  mut filter: LineFilter,
+  stopped: &AtomicBool,
+  channel: &Channel<LogStreamMessage>,
+) {
+  let mut buffer = vec![0; READ_BUFFER_BYTES];
+  let mut receiver_gone = false;
+  let outcome = loop {
+    if stopped.load(Ordering::SeqCst) || receiver_gone {
+      break Ok(true);
+    }
+    match stream.read(&mut buffer) {
+      Ok(StreamRead::Data(read)) => filter.push(&buffer[..read], |line, level| {
+        receiver_gone |= channel.send(LogStreamMessage::Line { line, level }).is_err();
+      }),
+      Ok(StreamRead::Idle) => std::thread::sleep(IDLE_POLL),
+      Ok(StreamRead::Closed) => {
+        filter.finish(|line, level| {
+          let _ = channel.send(LogStreamMessage::Line { line, level });
+        });
+        break Ok(false);
+      }
+      Err(error) => break Err(error),
+    }
+  };
+  stream.close();
+  let message = match outcome {
+    Ok(stopped) => LogStreamMessage::Ended { stopped },
+    Err(error) => LogStreamMessage::Failed { error },
+  };
+  let _ = channel.send(message);
+}
+
+/// Starts streaming a container's logs and returns the stream id to pass to
+/// `stop_container_logs`. Connection errors are returned directly; later
+/// failures arrive on the channel as `failed`.
+#[tauri::command]
+pub async fn stream_container_logs(
+  app: AppHandle,
+  profile_id: String,
+  container: String,
+  tail: Option<u32>,
+  min_level: Option<LogLevel>,
+  on_message: Channel<LogStreamMessage>,
+) -> CommandResult<String> {
+  let container = container.trim().to_string();
+  if !valid_container_name(&container) {
+    return Err(CommandError::invalid_input("A valid container name is required."));
+  }
+  let tail = tail.unwrap_or(DEFAULT_TAIL_LINES).min(MAX_TAIL_LINES);
+  let command = format!("docker logs --follow --tail {tail} {} 2>&1", shell_escape(&container));
+  let session_app = app.clone();
+  let stream = tauri::async_runtime::spawn_blocking(move || {
+    let (_, session) = open_session(&session_app, &profile_id)?;
+    session.stream(&command)
+  })
+  .await
+  .map_err(|error| CommandError::internal(format!("Log stream task failed: {error}")))??;
+
+  let stream_id = uuid::Uuid::new_v4().to_string();
+  let stopped = app.state::<LogStreamStore>().register(&stream_id);
+  let thread_stream_id = stream_id.clone();
+  let filter = LineFilter {
+    min_level,
+    current_level: None,
+    pending: Vec::new(),
+  };
+  std::thread::spawn(move || {
+    pump(stream, filter, &stopped, &on_message);
+    app.state::<LogStreamStore>().stop(&thread_stream_id);
+  });
+  Ok(stream_id)
+}
+
+/// Returns whether a running stream was stopped.
+#[tauri::command]
+pub fn stop_container_logs(store: State<'_, LogStreamStore>, stream_id: String) -> bool {
+  store.stop(&stream_id)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn filter(min_level: Option<LogLevel>) -> LineFilter {
+    LineFilter {
+      min_level,
+      current_level: None,
+      pending: Vec::new(),
+    }
+  }
+
+  fn collect(filter: &mut LineFilter, chunks: &[&str]) -> Vec<String> {
+    let mut lines = Vec::new();
+    for chunk in chunks {
+      filter.push(chunk.as_bytes(), |line, _| lines.push(line));
+    }
+    filter.finish(|line, _| lines.push(line));
+    lines
+  }
+
+  #[test]
+  fn splits_lines_across_chunks() {
+    let lines = collect(&mut filter(None), &["first li", "ne\r\nsecond\n"]);
+    assert_eq!(lines, ["first line", "second"]);
+  }
+
+  #[test]
+  fn finish_flushes_unterminated_line() {
+    let mut filter = filter(None);
+    let lines = collect(&mut filter, &["complete\npartial"]);
+    assert_eq!(lines, ["complete", "partial"]);
+    assert!(filter.pending.is_empty());
+  }
+
+  #[test]
+  fn finish_applies_level_filter() {
+    let lines = collect(
+      &mut filter(Some(LogLevel::Warning)),
+      &[
+        "2024-05-01 12:00:00,123 - synapse.access - 450 - INFO - GET-1 - ok\n",
+        "2024-05-01 12:00:01,456 - synapse.app - 12 - ERROR - main - boom\n",
+        "Traceback (most recent call last):",
+      ],
+    );
+    assert_eq!(
+      lines,
+      [
+        "2024-05-01 12:00:01,456 - synapse.app - 12 - ERROR - main - boom",
+        "Traceback (most recent call last):",
+      ]
+    );
+  }
+}
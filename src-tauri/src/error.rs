@@ -0,0 +1,215 @@
+//! Error type returned by every Tauri command.
+//!
+//! Errors serialize as `{ kind, message, status?, errcode?, details? }` so the
+//! frontend can branch on `kind` instead of matching message text.
+
+use reqwest::{Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorKind {
+  /// The request never reached the server or the connection dropped.
+  Network,
+  /// The server or SSH session did not answer in time.
+  Timeout,
+  /// Missing, expired or unknown access token (HTTP 401).
+  Auth,
+  /// Authenticated but not allowed, e.g. the user is not a server admin (HTTP 403).
+  Forbidden,
+  NotFound,
+  /// Synapse rejected the request with a Matrix `errcode` not covered above.
+  Synapse,
+  /// Non-success HTTP status without a Matrix error body.
+  Http,
+  /// The SSH session or channel could not be set up (handshake, channel open).
+  SshLaunch,
+  /// SSH authentication was rejected by the remote host.
+  SshAuth,
+  /// First connection to an SSH host; `details` carries the fingerprint to approve.
+  HostKeyUnknown,
+  /// The SSH host presented a different key than the one trusted earlier.
+  HostKeyMismatch,
+  /// The remote command ran but exited unsuccessfully.
+  Ssh,
+  /// The OS keychain refused to store or return a credential.
+  Keychain,
+  /// The caller stopped waiting on a long-running operation.
+  Cancelled,
+  /// A response could not be decoded.
+  Parse,
+  /// The command arguments were rejected before any I/O happened.
+  InvalidInput,
+  Internal,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandError {
+  pub kind: ErrorKind,
+  pub message: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub status: Option<u16>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub errcode: Option<String>,
+  /// Kind-specific structured data, e.g. the presented host key.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub details: Option<Value>,
+}
+
+pub type CommandResult<T> = Result<T, CommandError>;
+
+#[derive(Deserialize)]
+struct MatrixErrorBody {
+  #[serde(default)]
+  errcode: Option<String>,
+  #[serde(default)]
+  error: Option<Value>,
+}
+
+fn kind_for_response(status: StatusCode, errcode: Option<&str>) -> ErrorKind {
+  match errcode {
+    Some("M_UNKNOWN_TOKEN" | "M_MISSING_TOKEN") => return ErrorKind::Auth,
+    Some("M_FORBIDDEN") => return ErrorKind::Forbidden,
+    Some("M_NOT_FOUND") => return ErrorKind::NotFound,
+    _ => {}
+  }
+  match status {
+    StatusCode::UNAUTHORIZED => ErrorKind::Auth,
+    StatusCode::FORBIDDEN => ErrorKind::Forbidden,
+    StatusCode::NOT_FOUND => ErrorKind::NotFound,
+    StatusCode::REQUEST_TIMEOUT | StatusCode::GATEWAY_TIMEOUT => ErrorKind::Timeout,
+    _ if errcode.is_some() => ErrorKind::Synapse,
+    _ => ErrorKind::Http,
+  }
+}
+
+impl CommandError {
+  pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+    Self {
+      kind,
+      message: message.into(),
+      status: None,
+      errcode: None,
+      details: None,
+    }
+  }
+
+  pub fn with_details(mut self, details: impl Serialize) -> Self {
+    self.details = serde_json::to_value(details).ok();
+    self
+  }
+
+  pub fn invalid_input(message: impl Into<String>) -> Self {
+    Self::new(ErrorKind::InvalidInput, message)
+  }
+
+  pub fn parse(message: impl Into<String>) -> Self {
+    Self::new(ErrorKind::Parse, message)
+  }
+
+  pub fn internal(message: impl Into<String>) -> Self {
+    Self::new(ErrorKind::Internal, message)
+  }
+
+  /// Maps a transport failure, keeping timeouts distinct from other network errors.
+  pub fn from_reqwest(error: reqwest::Error, context: &str) -> Self {
+    let kind = if error.is_timeout() {
+      ErrorKind::Timeout
+    } else if error.is_decode() {
+      ErrorKind::Parse
+    } else {
+      ErrorKind::Network
+    };
+    Self::new(kind, format!("Network error while {context}: {error}"))
+  }
+
+  /// Builds an error from a non-success HTTP response, extracting the Matrix
+  /// `errcode`/`error` pair when the body carries one.
+  pub async fn from_response(response: Response) -> Self {
+    let status = response.status();
+    let text = response.text().await.unwrap_or_default();
+    let body = serde_json::from_str::<MatrixErrorBody>(&text).ok();
+    let errcode = body
+      .as_ref()
+      .and_then(|body| body.errcode.clone())
+      .filter(|errcode| !errcode.trim().is_empty());
+    let error_text = body
+      .as_ref()
+      .and_then(|body| body.error.as_ref())
+      .and_then(Value::as_str)
+      .filter(|message| !message.trim().is_empty());
+
+    let message = match error_text {
+      Some(message) => format!("{}: {}", status, message),
+      None if text.is_empty() => format!("HTTP {}", status),
+      None => format!("{}: {}", status, text),
+    };
+
+    Self {
+      kind: kind_for_response(status, errcode.as_deref()),
+      message,
+      status: Some(status.as_u16()),
+      errcode,
+      details: None,
+    }
+  }
+}
+
+impl fmt::Display for CommandError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.message)
+  }
+}
+
+impl std::error::Error for CommandError {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn error_for(status: u16, body: &str) -> CommandError {
+    let response = tauri::http::Response::builder()
+      .status(status)
+      .body(body.to_string())
+      .expect("response fixture");
+    tauri::async_runtime::block_on(CommandError::from_response(Response::from(response)))
+  }
+
+  #[test]
+  fn from_response_reads_matrix_error_body() {
+    let error = error_for(403, r#"{"errcode":"M_FORBIDDEN","error":"You are not a server admin"}"#);
+    assert_eq!(error.kind, ErrorKind::Forbidden);
+    assert_eq!(error.status, Some(403));
+    assert_eq!(error.errcode.as_deref(), Some("M_FORBIDDEN"));
+    assert_eq!(error.message, "403 Forbidden: You are not a server admin");
+  }
+
+  #[test]
+  fn from_response_errcode_overrides_status() {
+    let error = error_for(400, r#"{"errcode":"M_UNKNOWN_TOKEN","error":"Invalid token"}"#);
+    assert_eq!(error.kind, ErrorKind::Auth);
+  }
+
+  #[test]
+  fn from_response_unknown_errcode_is_synapse() {
+    let error = error_for(400, r#"{"errcode":"M_BAD_JSON","error":"Bad body"}"#);
+    assert_eq!(error.kind, ErrorKind::Synapse);
+    assert_eq!(error.errcode.as_deref(), Some("M_BAD_JSON"));
+  }
+
+  #[test]
+  fn from_response_without_matrix_body() {
+    let error = error_for(502, "<html>Bad Gateway</html>");
+    assert_eq!(error.kind, ErrorKind::Http);
+    assert!(error.errcode.is_none());
+    assert_eq!(error.message, "502 Bad Gateway: <html>Bad Gateway</html>");
+
+    let empty = error_for(401, "");
+    assert_eq!(empty.kind, ErrorKind::Auth);
+    assert_eq!(empty.message, "HTTP 401 Unauthorized");
+
+    assert_eq!(error_for(504, "").kind, ErrorKind::Timeout);
+  }
+}
@@ -0,0 +1,525 @@
+//! Health snapshot history.
+//!
+//! Every snapshot collected for a saved server profile is kept in a SQLite
+//! database in the app data dir. The headline metrics get their own columns
+//! so range queries and downsampling run in SQL; the full snapshot is kept as
+//! JSON alongside them. Rows older than [`MAX_SNAPSHOT_AGE_MS`] or beyond
+//! [`MAX_SNAPSHOTS_PER_PROFILE`] are pruned on a profile's first insert after
+//! launch and then every [`PRUNE_EVERY_INSERTS`] inserts.
+
+use crate::error::{CommandError, CommandResult};
+use crate::now_millis;
+use crate::server_health::ServerHealthSnapshot;
+use rusqlite::{params, Connection, ErrorCode, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use tauri::{AppHandle, Manager};
+
+pub const HEALTH_HISTORY_FILE: &str = "health_history.sqlite3";
+/// Thirty days.
+const MAX_SNAPSHOT_AGE_MS: u64 = 30 * 24 * 60 * 60 * 1000;
+/// About a week of snapshots at the UI's 10-second auto-refresh.
+const MAX_SNAPSHOTS_PER_PROFILE: u32 = 60_000;
+/// Lets a profile overshoot the cap by at most this many rows between prunes.
+const PRUNE_EVERY_INSERTS: u32 = 100;
+const DEFAULT_SNAPSHOT_LIMIT: u32 = 100;
+const MAX_SNAPSHOT_LIMIT: u32 = 1_000;
+const DEFAULT_SERIES_BUCKETS: u32 = 120;
+const MAX_SERIES_BUCKETS: u32 = 2_000;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS health_snapshots (
+  id INTEGER PRIMARY KEY,
+  profile_id TEXT NOT NULL,
+  captured_at INTEGER NOT NULL,
+  cpu_percent REAL,
+  memory_used_bytes INTEGER,
+  memory_total_bytes INTEGER,
+  disk_used_bytes INTEGER,
+  disk_total_bytes INTEGER,
+  db_size_bytes INTEGER,
+  db_connections INTEGER,
+  snapshot TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS health_snapshots_profile_time
+  ON health_snapshots (profile_id, captured_at);
+";
+
+fn storage_error(context: &str, error: rusqlite::Error) -> CommandError {
+  CommandError::internal(format!("{context}: {error}"))
+}
+
+fn is_corrupt(error: &rusqlite::Error) -> bool {
+  matches!(
+    error.sqlite_error_code(),
+    Some(ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase)
+  )
+}
+
+/// Renames a corrupt database, and any rollback journal that would otherwise
+/// be replayed into its replacement, out of the way.
+fn move_aside(path: &Path) -> CommandResult<()> {
+  let suffix = format!(".corrupt-{}", now_millis());
+  let journal = PathBuf::from(format!("{}-journal", path.display()));
+  for file in [path.to_path_buf(), journal] {
+    if !file.exists() {
+      continue;
+    }
+    let mut target = file.clone().into_os_string();
+    target.push(&suffix);
+    std::fs::rename(&file, &target).map_err(|error| {
+      CommandError::internal(format!("Unable to move {} aside: {error}", file.display()))
+    })?;
+    log::warn!("Moved {} to {}", file.display(), PathBuf::from(target).display());
+  }
+  Ok(())
+}
+
+/// Drops a profile's rows captured before `oldest` and all but its newest
+/// `max_rows`. Both deletes walk the `(profile_id, captured_at)` index.
+fn prune(connection: &Connection, profile_id: &str, oldest: u64, max_rows: u32) -> CommandResult<()> {
+  connection
+    .execute(
+      "DELETE FROM health_snapshots WHERE profile_id = ?1 AND captured_at < ?2",
+      params![profile_id, oldest as i64],
+    )
+    .map_err(|error| storage_error("Unable to prune health history", error))?;
+  connection
+    .execute(
+      "DELETE FROM health_snapshots
+       WHERE profile_id = ?1 AND captured_at <= (
+         SELECT captured_at FROM health_snapshots WHERE profile_id = ?1
+         ORDER BY captured_at DESC LIMIT 1 OFFSET ?2
+       )",
+      params![profile_id, max_rows],
+    )
+    .map_err(|error| storage_error("Unable to prune health history", error))?;
+  Ok(())
+}
+
+/// Metrics that can be charted over time.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthMetric {
+  CpuPercent,
+  MemoryUsedBytes,
+  MemoryPercent,
+  DiskUsedBytes,
+  DiskPercent,
+  DbSizeBytes,
+  DbConnections,
+}
+
+impl HealthMetric {
+  /// SQL expression over a `health_snapshots` row. Static strings only, so
+  /// building queries from them is injection-safe.
+  fn expression(self) -> &'static str {
+    match self {
+      HealthMetric::CpuPercent => "cpu_percent",
+      HealthMetric::MemoryUsedBytes => "memory_used_bytes",
+      HealthMetric::MemoryPercent => "100.0 * memory_used_bytes / NULLIF(memory_total_bytes, 0)",
+      HealthMetric::DiskUsedBytes => "disk_used_bytes",
+      HealthMetric::DiskPercent => "100.0 * disk_used_bytes / NULLIF(disk_total_bytes, 0)",
+      HealthMetric::DbSizeBytes => "db_size_bytes",
+      HealthMetric::DbConnections => "db_connections",
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredHealthSnapshot {
+  pub captured_at: u64,
+  pub snapshot: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthSeriesPoint {
+  pub bucket_start: u64,
+  pub avg: f64,
+  pub min: f64,
+  pub max: f64,
+  pub samples: u32,
+}
+
+/// A downsampled series. `first` and `last` are the raw samples at either end
+/// of the range, so `last - first` gives the exact change over it.
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthSeries {
+  pub metric: HealthMetric,
+  pub from: u64,
+  pub to: u64,
+  pub bucket_ms: u64,
+  pub points: Vec<HealthSeriesPoint>,
+  pub first: Option<f64>,
+  pub last: Option<f64>,
+}
+
+pub struct HealthHistoryStore {
+  connection: Mutex<Connection>,
+  /// Inserts per profile since its last prune.
+  inserts_since_prune: Mutex<HashMap<String, u32>>,
+}
+
+impl HealthHistoryStore {
+  /// Opens the history database. This never fails: history is a convenience
+  /// and must not keep the app from starting. A corrupt file is moved aside
+  /// as `<file>.corrupt-<ms>` and replaced with an empty one; if the database
+  /// still cannot be opened (locked, unwritable dir, ...), history is kept in
+  /// memory for this session only.
+  pub fn open(path: &Path) -> Self {
+    let connection = Self::open_file(path).unwrap_or_else(|error| {
+      log::warn!("Health history is kept in memory for this session: {error}");
+      let connection = Connection::open_in_memory().expect("in-memory SQLite database");
+      connection
+        .execute_batch(SCHEMA)
+        .expect("health history schema on an in-memory database");
+      connection
+    });
+    Self {
+      connection: Mutex::new(connection),
+      inserts_since_prune: Mutex::new(HashMap::new()),
+    }
+  }
+
+  fn open_file(path: &Path) -> CommandResult<Connection> {
+    if let Some(parent) = path.parent() {
+      std::fs::create_dir_all(parent).map_err(|error| {
+        CommandError::internal(format!("Unable to create {}: {error}", parent.display()))
+      })?;
+    }
+    match Self::connect(path) {
+      Ok(connection) => Ok(connection),
+      Err(error) if is_corrupt(&error) => {
+        log::error!("Health history at {} is corrupt: {error}", path.display());
+        move_aside(path)?;
+        Self::connect(path).map_err(|error| storage_error("Unable to recreate health history", error))
+      }
+      Err(error) => Err(storage_error("Unable to open health history", error)),
+    }
+  }
+
+  fn connect(path: &Path) -> rusqlite::Result<Connection> {
+    let connection = Connection::open(path)?;
+    connection.execute_batch(SCHEMA)?;
+    Ok(connection)
+  }
+
+  fn lock(&self) -> MutexGuard<'_, Connection> {
+    self.connection.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+
+  /// Stores a snapshot and prunes the profile's history to the retention limits.
+  pub fn record(&self, profile_id: &str, snapshot: &ServerHealthSnapshot) -> CommandResult<()> {
+    let host = &snapshot.host;
+    let serialized = serde_json::to_string(snapshot)
+      .map_err(|error| CommandError::internal(format!("Unable to serialize health snapshot: {error}")))?;
+    let connection = self.lock();
+    connection
+      .execute(
+        "INSERT INTO health_snapshots (
+           profile_id, captured_at, cpu_percent, memory_used_bytes, memory_total_bytes,
+           disk_used_bytes, disk_total_bytes, db_size_bytes, db_connections, snapshot
+         ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+          profile_id,
+          snapshot.captured_at as i64,
+          host.cpu_percent,
+          host.memory_used_bytes as i64,
+          host.memory_total_bytes as i64,
+          host.disk_used_bytes as i64,
+          host.disk_total_bytes as i64,
+          snapshot.database.size_bytes.map(|size| size as i64),
+          snapshot.database.active_connections.map(|connections| connections as i64),
+          serialized,
+        ],
+      )
+      .map_err(|error| storage_error("Unable to store health snapshot", error))?;
+    if self.prune_due(profile_id) {
+      prune(
+        &connection,
+        profile_id,
+        now_millis().saturating_sub(MAX_SNAPSHOT_AGE_MS),
+        MAX_SNAPSHOTS_PER_PROFILE,
+      )?;
+    }
+    Ok(())
+  }
+
+  /// Counts an insert and reports whether the profile should be pruned now.
+  fn prune_due(&self, profile_id: &str) -> bool {
+    let mut counts = self
+      .inserts_since_prune
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let count = counts.entry(profile_id.to_string()).or_insert(0);
+    let due = *count == 0;
+    *count = (*count + 1) % PRUNE_EVERY_INSERTS;
+    due
+  }
+
+  /// Snapshots in `[from, to]`, newest first.
+  fn snapshots(
+    &self,
+    profile_id: &str,
+    from: u64,
+    to: u64,
+    limit: u32,
+  ) -> CommandResult<Vec<StoredHealthSnapshot>> {
+    let connection = self.lock();
+    let mut statement = connection
+      .prepare(
+        "SELECT captured_at, snapshot FROM health_snapshots
+         WHERE profile_id = ?1 AND captured_at BETWEEN ?2 AND ?3
+         ORDER BY captured_at DESC LIMIT ?4",
+      )
+      .map_err(|error| storage_error("Unable to query health history", error))?;
+    let rows = statement
+      .query_map(params![profile_id, from as i64, to as i64, limit], |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+      })
+      .map_err(|error| storage_error("Unable to query health history", error))?;
+    rows
+      .map(|row| {
+        let (captured_at, snapshot) =
+          row.map_err(|error| storage_error("Unable to read health history", error))?;
+        Ok(StoredHealthSnapshot {
+          captured_at: captured_at as u64,
+          snapshot: serde_json::from_str(&snapshot)
+            .map_err(|error| CommandError::parse(format!("Stored health snapshot is unreadable: {error}")))?,
+        })
+      })
+      .collect()
+  }
+
+  fn series(
+    &self,
+    profile_id: &str,
+    metric: HealthMetric,
+    from: u64,
+    to: u64,
+    buckets: u32,
+  ) -> CommandResult<HealthSeries> {
+    let bucket_ms = ((to - from) / u64::from(buckets)).max(1);
+    let expression = metric.expression();
+    let connection = self.lock();
+    let mut statement = connection
+      .prepare(&format!(
+        "SELECT (captured_at - ?2) / ?4 AS bucket, AVG({expression}), MIN({expression}),
+                MAX({expression}), COUNT({expression})
+         FROM health_snapshots
+         WHERE profile_id = ?1 AND captured_at BETWEEN ?2 AND ?3 AND {expression} IS NOT NULL
+         GROUP BY bucket ORDER BY bucket"
+      ))
+      .map_err(|error| storage_error("Unable to query health series", error))?;
+    let points = statement
+      .query_map(params![profile_id, from as i64, to as i64, bucket_ms as i64], |row| {
+        Ok(HealthSeriesPoint {
+          bucket_start: from + row.get::<_, i64>(0)? as u64 * bucket_ms,
+          avg: row.get(1)?,
+          min: row.get(2)?,
+          max: row.get(3)?,
+          samples: row.get(4)?,
+        })
+      })
+      .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+      .map_err(|error| storage_error("Unable to read health series", error))?;
+
+    let edge = |order: &str| {
+      connection
+        .query_row(
+          &format!(
+            "SELECT {expression} FROM health_snapshots
+             WHERE profile_id = ?1 AND captured_at BETWEEN ?2 AND ?3 AND {expression} IS NOT NULL
+             ORDER BY captured_at {order} LIMIT 1"
+          ),
+          params![profile_id, from as i64, to as i64],
+          |row| row.get::<_, f64>(0),
+        )
+        .optional()
+        .map_err(|error| storage_error("Unable to read health series", error))
+    };
+    Ok(HealthSeries {
+      metric,
+      from,
+      to,
+      bucket_ms,
+      first: edge("ASC")?,
+      last: edge("DESC")?,
+      points,
+    })
+  }
+
+  pub fn clear(&self, profile_id: &str) -> CommandResult<u64> {
+    self
+      .lock()
+      .execute("DELETE FROM health_snapshots WHERE profile_id = ?1", params![profile_id])
+      .map(|removed| removed as u64)
+      .map_err(|error| storage_error("Unable to clear health history", error))
+  }
+}
+
+/// Defaults a query range to the last 24 hours ending now.
+fn resolve_range(from: Option<u64>, to: Option<u64>) -> CommandResult<(u64, u64)> {
+  let to = to.unwrap_or_else(now_millis);
+  let from = from.unwrap_or_else(|| to.saturating_sub(24 * 60 * 60 * 1000));
+  if from >= to {
+    return Err(CommandError::invalid_input("`from` must be earlier than `to`."));
+  }
+  Ok((from, to))
+}
+
+async fn with_store<T: Send + 'static>(
+  app: AppHandle,
+  task: impl FnOnce(&HealthHistoryStore) -> CommandResult<T> + Send + 'static,
+) -> CommandResult<T> {
+  tauri::async_runtime::spawn_blocking(move || task(&app.state::<HealthHistoryStore>()))
+    .await
+    .map_err(|error| CommandError::internal(format!("Health history task failed: {error}")))?
+}
+
+/// Raw snapshots for a profile, newest first. Defaults to the last 24 hours.
+#[tauri::command]
+pub async fn list_health_snapshots(
+  app: AppHandle,
+  profile_id: String,
+  from: Option<u64>,
+  to: Option<u64>,
+  limit: Option<u32>,
+) -> CommandResult<Vec<StoredHealthSnapshot>> {
+  let (from, to) = resolve_range(from, to)?;
+  let limit = limit.unwrap_or(DEFAULT_SNAPSHOT_LIMIT).clamp(1, MAX_SNAPSHOT_LIMIT);
+  with_store(app, move |store| store.snapshots(&profile_id, from, to, limit)).await
+}
+
+/// One metric over `[from, to]`, averaged into at most `buckets` points.
+#[tauri::command]
+pub async fn health_metric_series(
+  app: AppHandle,
+  profile_id: String,
+  metric: HealthMetric,
+  from: Option<u64>,
+  to: Option<u64>,
+  buckets: Option<u32>,
+) -> CommandResult<HealthSeries> {
+  let (from, to) = resolve_range(from, to)?;
+  let buckets = buckets.unwrap_or(DEFAULT_SERIES_BUCKETS).clamp(1, MAX_SERIES_BUCKETS);
+  with_store(app, move |store| store.series(&profile_id, metric, from, to, buckets)).await
+}
+
+/// Deletes a profile's stored history and returns the number of snapshots removed.
+#[tauri::command]
+pub async fn clear_health_history(app: AppHandle, profile_id: String) -> CommandResult<u64> {
+  with_store(app, move |store| store.clear(&profile_id)).await
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("fray-{name}-{}-{}", std::process::id(), now_millis()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  fn snapshot_count(store: &HealthHistoryStore) -> i64 {
+    store
+      .lock()
+      .query_row("SELECT COUNT(*) FROM health_snapshots", [], |row| row.get(0))
+      .unwrap()
+  }
+
+  fn insert_rows(store: &HealthHistoryStore, profile_id: &str, captured_at: impl IntoIterator<Item = u64>) {
+    let connection = store.lock();
+    for captured_at in captured_at {
+      connection
+        .execute(
+          "INSERT INTO health_snapshots (profile_id, captured_at, snapshot) VALUES (?1, ?2, '{}')",
+          params![profile_id, captured_at as i64],
+        )
+        .unwrap();
+    }
+  }
+
+  fn captured_times(store: &HealthHistoryStore, profile_id: &str) -> Vec<i64> {
+    let connection = store.lock();
+    let mut statement = connection
+      .prepare("SELECT captured_at FROM health_snapshots WHERE profile_id = ?1 ORDER BY captured_at")
+      .unwrap();
+    let times = statement
+      .query_map([profile_id], |row| row.get(0))
+      .unwrap()
+      .collect::<Result<_, _>>()
+      .unwrap();
+    times
+  }
+
+  #[test]
+  fn prune_applies_age_and_row_cap_per_profile() {
+    let dir = scratch_dir("prune-history");
+    let store = HealthHistoryStore::open(&dir.join(HEALTH_HISTORY_FILE));
+    insert_rows(&store, "hq", 1..=10);
+    insert_rows(&store, "other", 1..=10);
+
+    prune(&store.lock(), "hq", 3, 5).unwrap();
+    assert_eq!(captured_times(&store, "hq"), [6, 7, 8, 9, 10]);
+    prune(&store.lock(), "hq", 8, 5).unwrap();
+    assert_eq!(captured_times(&store, "hq"), [8, 9, 10]);
+    assert_eq!(captured_times(&store, "other").len(), 10);
+
+    drop(store);
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn prune_runs_on_first_insert_then_every_n() {
+    let dir = scratch_dir("prune-due-history");
+    let store = HealthHistoryStore::open(&dir.join(HEALTH_HISTORY_FILE));
+    assert!(store.prune_due("hq"));
+    assert!((1..PRUNE_EVERY_INSERTS).all(|_| !store.prune_due("hq")));
+    assert!(store.prune_due("hq"));
+    assert!(store.prune_due("other"));
+
+    drop(store);
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn corrupt_database_is_moved_aside_and_recreated() {
+    let dir = scratch_dir("corrupt-history");
+    let path = dir.join(HEALTH_HISTORY_FILE);
+    std::fs::write(&path, b"definitely not a sqlite database, just enough bytes to have a header").unwrap();
+
+    let store = HealthHistoryStore::open(&path);
+    assert_eq!(snapshot_count(&store), 0);
+    let moved: Vec<String> = std::fs::read_dir(&dir)
+      .unwrap()
+      .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+      .filter(|name| name.starts_with(&format!("{HEALTH_HISTORY_FILE}.corrupt-")))
+      .collect();
+    assert_eq!(moved.len(), 1);
+    assert!(path.exists());
+
+    drop(store);
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn unopenable_database_falls_back_to_memory() {
+    let dir = scratch_dir("unopenable-history");
+    // A directory where the file should be cannot be opened as a database
+    // and is not corrupt, so it is left alone.
+    let path = dir.join(HEALTH_HISTORY_FILE);
+    std::fs::create_dir(&path).unwrap();
+
+    let store = HealthHistoryStore::open(&path);
+    assert_eq!(snapshot_count(&store), 0);
+    assert!(path.is_dir());
+
+    drop(store);
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+}
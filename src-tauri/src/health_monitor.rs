@@ -0,0 +1,635 @@
+//! Background health polling with threshold alerts.
+//!
+//! Each enabled monitor polls one saved server profile on its own interval,
+//! evaluates the alert rules against the snapshot and raises a desktop
+//! notification plus a `health-alert` event. An alert fires once when its
+//! condition starts holding and again only after the condition has cleared
+//! and the monitor's cooldown has passed, so a flapping container is reported
+//! at most once per cooldown. Monitor settings are kept in
+//! `health_monitors.json` in the app data dir.
+
+use crate::app_data::{read_json_file, write_json_file};
+use crate::error::{CommandError, CommandResult};
+use crate::now_millis;
+use crate::server_health::{poll_profile_health, ServerHealthSnapshot};
+use crate::server_profiles::ServerProfileStore;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_notification::NotificationExt;
+use tokio::sync::Notify;
+
+pub const HEALTH_MONITORS_FILE: &str = "health_monitors.json";
+const HEALTH_ALERT_EVENT: &str = "health-alert";
+const DEFAULT_INTERVAL_SECS: u64 = 300;
+const MIN_INTERVAL_SECS: u64 = 30;
+const DEFAULT_COOLDOWN_SECS: u64 = 30 * 60;
+/// One week.
+const MAX_COOLDOWN_SECS: u64 = 7 * 24 * 60 * 60;
+const DEFAULT_DISK_PERCENT: f64 = 90.0;
+
+fn default_disk_percent() -> Option<f64> {
+  Some(DEFAULT_DISK_PERCENT)
+}
+
+fn default_enabled() -> bool {
+  true
+}
+
+/// Alert thresholds. A `None` threshold disables that rule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRules {
+  #[serde(default = "default_disk_percent")]
+  pub disk_percent_above: Option<f64>,
+  #[serde(default)]
+  pub postgres_connections_above: Option<u64>,
+  #[serde(default = "default_enabled")]
+  pub container_not_running: bool,
+  #[serde(default = "default_enabled")]
+  pub container_unhealthy: bool,
+  #[serde(default = "default_enabled")]
+  pub synapse_version_changed: bool,
+}
+
+impl Default for AlertRules {
+  fn default() -> Self {
+    Self {
+      disk_percent_above: default_disk_percent(),
+      postgres_connections_above: None,
+      container_not_running: true,
+      container_unhealthy: true,
+      synapse_version_changed: true,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthMonitor {
+  pub profile_id: String,
+  pub enabled: bool,
+  pub interval_secs: u64,
+  /// Minimum time between two notifications for the same alert.
+  pub cooldown_secs: u64,
+  pub rules: AlertRules,
+  /// Last Synapse version seen, persisted so an upgrade while Fray was closed
+  /// is still reported.
+  #[serde(default)]
+  pub last_synapse_version: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HealthMonitorInput {
+  pub profile_id: String,
+  #[serde(default = "default_enabled")]
+  pub enabled: bool,
+  #[serde(default)]
+  pub interval_secs: Option<u64>,
+  #[serde(default)]
+  pub cooldown_secs: Option<u64>,
+  #[serde(default)]
+  pub rules: Option<AlertRules>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertRule {
+  DiskUsage,
+  PostgresConnections,
+  ContainerNotRunning,
+  ContainerUnhealthy,
+  SynapseVersionChanged,
+}
+
+impl AlertRule {
+  fn title(self) -> &'static str {
+    match self {
+      AlertRule::DiskUsage => "Disk almost full",
+      AlertRule::PostgresConnections => "Too many Postgres connections",
+      AlertRule::ContainerNotRunning => "Container not running",
+      AlertRule::ContainerUnhealthy => "Container unhealthy",
+      AlertRule::SynapseVersionChanged => "Synapse version changed",
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthAlert {
+  pub profile_id: String,
+  pub profile_name: String,
+  pub rule: AlertRule,
+  /// Container the alert is about, for the per-container rules.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub subject: Option<String>,
+  pub message: String,
+  pub raised_at: u64,
+}
+
+impl HealthAlert {
+  /// Debounce key: one per rule and container.
+  fn key(&self) -> String {
+    match &self.subject {
+      Some(subject) => format!("{:?}:{subject}", self.rule),
+      None => format!("{:?}", self.rule),
+    }
+  }
+}
+
+/// Conditions currently holding for a snapshot. The version rule is handled
+/// separately because it compares against the previous poll.
+fn evaluate(rules: &AlertRules, snapshot: &ServerHealthSnapshot) -> Vec<(AlertRule, Option<String>, String)> {
+  let mut firing = Vec::new();
+  let host = &snapshot.host;
+  if let Some(limit) = rules.disk_percent_above.filter(|_| host.disk_total_bytes > 0) {
+    let percent = host.disk_used_bytes as f64 / host.disk_total_bytes as f64 * 100.0;
+    if percent > limit {
+      firing.push((
+        AlertRule::DiskUsage,
+        None,
+        format!("Disk usage is {percent:.1}% (limit {limit:.0}%)."),
+      ));
+    }
+  }
+  if let (Some(limit), Some(connections)) = (
+    rules.postgres_connections_above,
+    snapshot.database.active_connections,
+  ) {
+    if connections > limit {
+      firing.push((
+        AlertRule::PostgresConnections,
+        None,
+        format!("Postgres has {connections} active connections (limit {limit})."),
+      ));
+    }
+  }
+  let containers = [
+    (&snapshot.matrix.container, &snapshot.matrix.status, &snapshot.matrix.health),
+    (&snapshot.database.container, &snapshot.database.status, &snapshot.database.health),
+  ];
+  for (name, status, health) in containers {
+    if rules.container_not_running && status != "running" {
+      firing.push((
+        AlertRule::ContainerNotRunning,
+        Some(name.clone()),
+        format!("{name} is {status}."),
+      ));
+    }
+    if rules.container_unhealthy && health == "unhealthy" {
+      firing.push((
+        AlertRule::ContainerUnhealthy,
+        Some(name.clone()),
+        format!("{name} reports unhealthy."),
+      ));
+    }
+  }
+  firing
+}
+
+/// Message for a Synapse version change, or `None` on the first poll and when
+/// the version is unchanged.
+///
+/// Version changes bypass [`Debounce`] on purpose: they are one-off events
+/// rather than conditions, and the persisted last version already reports
+/// each change exactly once. A cooldown would only hide a second change
+/// shortly after the first, such as a rollback of a bad upgrade.
+fn version_change(previous: Option<&str>, current: &str) -> Option<String> {
+  previous
+    .filter(|previous| *previous != current)
+    .map(|previous| format!("Synapse changed from {previous} to {current}."))
+}
+
+/// Per-monitor debounce state, kept for the lifetime of the polling task.
+#[derive(Default)]
+struct Debounce {
+  last_notified: HashMap<String, u64>,
+  /// Alerts already reported for the current occurrence of their condition.
+  reported: HashSet<String>,
+}
+
+impl Debounce {
+  /// Returns the alerts to raise at `now` and forgets conditions that cleared.
+  fn filter(&mut self, alerts: Vec<HealthAlert>, now: u64, cooldown_ms: u64) -> Vec<HealthAlert> {
+    let active: HashSet<String> = alerts.iter().map(HealthAlert::key).collect();
+    self.reported.retain(|key| active.contains(key));
+    alerts
+      .into_iter()
+      .filter(|alert| {
+        let key = alert.key();
+        let cooled_down = self
+          .last_notified
+          .get(&key)
+          .map_or(true, |last| now.saturating_sub(*last) >= cooldown_ms);
+        if self.reported.contains(&key) || !cooled_down {
+          return false;
+        }
+        self.last_notified.insert(key.clone(), now);
+        self.reported.insert(key);
+        true
+      })
+      .collect()
+  }
+}
+
+fn raise(app: &AppHandle, alert: &HealthAlert) {
+  if let Err(error) = app.emit(HEALTH_ALERT_EVENT, alert) {
+    log::warn!("Failed to emit health alert: {error}");
+  }
+  let result = app
+    .notification()
+    .builder()
+    .title(format!("{}: {}", alert.profile_name, alert.rule.title()))
+    .body(&alert.message)
+    .show();
+  if let Err(error) = result {
+    log::warn!("Failed to show health alert notification: {error}");
+  }
+}
+
+pub struct HealthMonitorStore {
+  path: PathBuf,
+  monitors: Mutex<Vec<HealthMonitor>>,
+  /// Stop signals for the running polling tasks, by profile id.
+  running: Mutex<HashMap<String, Arc<Notify>>>,
+}
+
+impl HealthMonitorStore {
+  pub fn load(path: PathBuf) -> Self {
+    let monitors = read_json_file(&path);
+    Self {
+      path,
+      monitors: Mutex::new(monitors),
+      running: Mutex::new(HashMap::new()),
+    }
+  }
+
+  fn lock(&self) -> MutexGuard<'_, Vec<HealthMonitor>> {
+    self.monitors.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+
+  fn get(&self, profile_id: &str) -> Option<HealthMonitor> {
+    self
+      .lock()
+      .iter()
+      .find(|monitor| monitor.profile_id == profile_id)
+      .cloned()
+  }
+
+  fn list(&self) -> Vec<HealthMonitor> {
+    self.lock().clone()
+  }
+
+  fn upsert(&self, monitor: HealthMonitor) {
+    let mut monitors = self.lock();
+    match monitors.iter_mut().find(|stored| stored.profile_id == monitor.profile_id) {
+      Some(stored) => *stored = monitor,
+      None => monitors.push(monitor),
+    }
+    write_json_file(&self.path, &*monitors);
+  }
+
+  fn record_version(&self, profile_id: &str, version: &str) {
+    let mut monitors = self.lock();
+    if let Some(monitor) = monitors.iter_mut().find(|monitor| monitor.profile_id == profile_id) {
+      monitor.last_synapse_version = Some(version.to_string());
+      write_json_file(&self.path, &*monitors);
+    }
+  }
+
+  /// Stops and removes a monitor. Returns whether one was configured.
+  pub fn remove(&self, profile_id: &str) -> bool {
+    self.stop(profile_id);
+    let mut monitors = self.lock();
+    let before = monitors.len();
+    monitors.retain(|monitor| monitor.profile_id != profile_id);
+    let removed = monitors.len() != before;
+    if removed {
+      write_json_file(&self.path, &*monitors);
+    }
+    removed
+  }
+
+  fn stop(&self, profile_id: &str) {
+    let stop = self
+      .running
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+      .remove(profile_id);
+    if let Some(stop) = stop {
+      stop.notify_one();
+    }
+  }
+
+  /// Registers a fresh stop signal, stopping any task already polling the profile.
+  fn begin(&self, profile_id: &str) -> Arc<Notify> {
+    self.stop(profile_id);
+    let stop = Arc::new(Notify::new());
+    self
+      .running
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+      .insert(profile_id.to_string(), stop.clone());
+    stop
+  }
+}
+
+async fn poll_once(app: &AppHandle, monitor: &HealthMonitor, debounce: &mut Debounce) {
+  let profile_name = match app.state::<ServerProfileStore>().get(&monitor.profile_id) {
+    Ok(profile) => profile.name,
+    Err(error) => {
+      log::warn!("Health monitor for {} skipped: {error}", monitor.profile_id);
+      return;
+    }
+  };
+  let task_app = app.clone();
+  let profile_id = monitor.profile_id.clone();
+  let snapshot = tauri::async_runtime::spawn_blocking(move || poll_profile_health(&task_app, &profile_id))
+    .await
+    .map_err(|error| CommandError::internal(format!("Health monitor task failed: {error}")))
+    .and_then(|result| result);
+  let snapshot = match snapshot {
+    Ok(snapshot) => snapshot,
+    Err(error) => {
+      log::warn!("Health monitor poll for {} failed: {error}", monitor.profile_id);
+      return;
+    }
+  };
+
+  let raised_at = now_millis();
+  let alert = |rule, subject, message| HealthAlert {
+    profile_id: monitor.profile_id.clone(),
+    profile_name: profile_name.clone(),
+    rule,
+    subject,
+    message,
+    raised_at,
+  };
+  let alerts = evaluate(&monitor.rules, &snapshot)
+    .into_iter()
+    .map(|(rule, subject, message)| alert(rule, subject, message))
+    .collect();
+  let cooldown_ms = monitor.cooldown_secs.saturating_mul(1000);
+  for pending in debounce.filter(alerts, raised_at, cooldown_ms) {
+    raise(app, &pending);
+  }
+
+  if let Some(version) = snapshot.matrix.version.as_deref() {
+    let previous = monitor.last_synapse_version.as_deref();
+    if previous != Some(version) {
+      let message = version_change(previous, version).filter(|_| monitor.rules.synapse_version_changed);
+      if let Some(message) = message {
+        raise(app, &alert(AlertRule::SynapseVersionChanged, None, message));
+      }
+      app
+        .state::<HealthMonitorStore>()
+        .record_version(&monitor.profile_id, version);
+    }
+  }
+}
+
+fn spawn_monitor(app: AppHandle, profile_id: String) {
+  let stop = app.state::<HealthMonitorStore>().begin(&profile_id);
+  tauri::async_runtime::spawn(async move {
+    let mut debounce = Debounce::default();
+    // Re-read each round so a stored version update is seen by the next poll.
+    while let Some(monitor) = app
+      .state::<HealthMonitorStore>()
+      .get(&profile_id)
+      .filter(|monitor| monitor.enabled)
+    {
+      poll_once(&app, &monitor, &mut debounce).await;
+      let interval = Duration::from_secs(monitor.interval_secs);
+      if tokio::time::timeout(interval, stop.notified()).await.is_ok() {
+        break;
+      }
+    }
+  });
+}
+
+/// Starts every enabled monitor saved by a previous run.
+pub fn start_all(app: &AppHandle) {
+  for monitor in app.state::<HealthMonitorStore>().list() {
+    if monitor.enabled {
+      spawn_monitor(app.clone(), monitor.profile_id);
+    }
+  }
+}
+
+#[tauri::command]
+pub fn list_health_monitors(store: State<'_, HealthMonitorStore>) -> Vec<HealthMonitor> {
+  store.list()
+}
+
+/// Creates or updates the monitor for a saved profile and restarts its polling.
+#[tauri::command]
+pub fn set_health_monitor(app: AppHandle, monitor: HealthMonitorInput) -> CommandResult<HealthMonitor> {
+  app.state::<ServerProfileStore>().get(&monitor.profile_id)?;
+  let interval_secs = monitor.interval_secs.unwrap_or(DEFAULT_INTERVAL_SECS);
+  if interval_secs < MIN_INTERVAL_SECS {
+    return Err(CommandError::invalid_input(format!(
+      "Polling interval must be at least {MIN_INTERVAL_SECS} seconds."
+    )));
+  }
+  let cooldown_secs = monitor.cooldown_secs.unwrap_or(DEFAULT_COOLDOWN_SECS);
+  if cooldown_secs > MAX_COOLDOWN_SECS {
+    return Err(CommandError::invalid_input(format!(
+      "Alert cooldown may be at most {MAX_COOLDOWN_SECS} seconds."
+    )));
+  }
+  let store = app.state::<HealthMonitorStore>();
+  let existing = store.get(&monitor.profile_id);
+  let saved = HealthMonitor {
+    enabled: monitor.enabled,
+    interval_secs,
+    cooldown_secs,
+    rules: monitor
+      .rules
+      .or_else(|| existing.as_ref().map(|existing| existing.rules.clone()))
+      .unwrap_or_default(),
+    last_synapse_version: existing.and_then(|existing| existing.last_synapse_version),
+    profile_id: monitor.profile_id,
+  };
+  store.upsert(saved.clone());
+  if saved.enabled {
+    spawn_monitor(app.clone(), saved.profile_id.clone());
+  } else {
+    store.stop(&saved.profile_id);
+  }
+  Ok(saved)
+}
+
+/// Stops and deletes a profile's monitor. Returns whether one was configured.
+#[tauri::command]
+pub fn remove_health_monitor(store: State<'_, HealthMonitorStore>, profile_id: String) -> bool {
+  store.remove(&profile_id)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::server_health::{DatabaseHealth, HostHealth, MatrixHealth};
+
+  const COOLDOWN_MS: u64 = 60_000;
+
+  fn snapshot() -> ServerHealthSnapshot {
+    ServerHealthSnapshot {
+      captured_at: 0,
+      host: HostHealth {
+        disk_total_bytes: 100,
+        disk_used_bytes: 50,
+        ..HostHealth::default()
+      },
+      matrix: MatrixHealth {
+        container: "fray-synapse".to_string(),
+        status: "running".to_string(),
+        health: "healthy".to_string(),
+        version: Some("1.100.0".to_string()),
+        room_count: None,
+        user_count: None,
+        joined_memberships: None,
+      },
+      database: DatabaseHealth {
+        container: "fray-postgres".to_string(),
+        status: "running".to_string(),
+        health: "healthy".to_string(),
+        database: "synapse".to_string(),
+        size_bytes: None,
+        active_connections: Some(10),
+      },
+      containers: Vec::new(),
+      errors: Vec::new(),
+    }
+  }
+
+  fn fired(rules: &AlertRules, snapshot: &ServerHealthSnapshot) -> Vec<(AlertRule, Option<String>)> {
+    evaluate(rules, snapshot)
+      .into_iter()
+      .map(|(rule, subject, _)| (rule, subject))
+      .collect()
+  }
+
+  fn alert(rule: AlertRule, subject: &str) -> HealthAlert {
+    HealthAlert {
+      profile_id: "hq".to_string(),
+      profile_name: "HQ".to_string(),
+      rule,
+      subject: Some(subject.to_string()),
+      message: String::new(),
+      raised_at: 0,
+    }
+  }
+
+  #[test]
+  fn healthy_snapshot_fires_nothing() {
+    assert!(fired(&AlertRules::default(), &snapshot()).is_empty());
+  }
+
+  #[test]
+  fn disk_threshold() {
+    let mut snapshot = snapshot();
+    snapshot.host.disk_used_bytes = 90;
+    assert!(fired(&AlertRules::default(), &snapshot).is_empty(), "at the limit is not above it");
+
+    snapshot.host.disk_used_bytes = 91;
+    assert_eq!(fired(&AlertRules::default(), &snapshot), [(AlertRule::DiskUsage, None)]);
+
+    let rules = AlertRules {
+      disk_percent_above: None,
+      ..AlertRules::default()
+    };
+    assert!(fired(&rules, &snapshot).is_empty());
+
+    snapshot.host.disk_total_bytes = 0;
+    assert!(fired(&AlertRules::default(), &snapshot).is_empty(), "unknown disk size");
+  }
+
+  #[test]
+  fn container_not_running_and_unhealthy() {
+    let mut snapshot = snapshot();
+    snapshot.matrix.status = "exited".to_string();
+    snapshot.database.health = "unhealthy".to_string();
+    assert_eq!(
+      fired(&AlertRules::default(), &snapshot),
+      [
+        (AlertRule::ContainerNotRunning, Some("fray-synapse".to_string())),
+        (AlertRule::ContainerUnhealthy, Some("fray-postgres".to_string())),
+      ]
+    );
+
+    let rules = AlertRules {
+      container_not_running: false,
+      container_unhealthy: false,
+      ..AlertRules::default()
+    };
+    assert!(fired(&rules, &snapshot).is_empty());
+  }
+
+  #[test]
+  fn postgres_connection_limit() {
+    let mut snapshot = snapshot();
+    let rules = AlertRules {
+      postgres_connections_above: Some(10),
+      ..AlertRules::default()
+    };
+    assert!(fired(&rules, &snapshot).is_empty());
+
+    snapshot.database.active_connections = Some(11);
+    assert_eq!(fired(&rules, &snapshot), [(AlertRule::PostgresConnections, None)]);
+    assert!(fired(&AlertRules::default(), &snapshot).is_empty(), "disabled by default");
+
+    snapshot.database.active_connections = None;
+    assert!(fired(&rules, &snapshot).is_empty());
+  }
+
+  #[test]
+  fn version_change_rule() {
+    assert_eq!(version_change(None, "1.100.0"), None, "first poll only records the version");
+    assert_eq!(version_change(Some("1.100.0"), "1.100.0"), None);
+    assert_eq!(
+      version_change(Some("1.99.0"), "1.100.0").as_deref(),
+      Some("Synapse changed from 1.99.0 to 1.100.0.")
+    );
+  }
+
+  #[test]
+  fn ongoing_condition_is_reported_once() {
+    let mut debounce = Debounce::default();
+    let down = || vec![alert(AlertRule::ContainerNotRunning, "fray-synapse")];
+    assert_eq!(debounce.filter(down(), 0, COOLDOWN_MS).len(), 1);
+    assert!(debounce.filter(down(), 10_000, COOLDOWN_MS).is_empty());
+    assert!(debounce.filter(down(), 10 * COOLDOWN_MS, COOLDOWN_MS).is_empty());
+  }
+
+  #[test]
+  fn flapping_container_is_suppressed_until_the_cooldown_passes() {
+    let mut debounce = Debounce::default();
+    let down = || vec![alert(AlertRule::ContainerNotRunning, "fray-synapse")];
+    assert_eq!(debounce.filter(down(), 0, COOLDOWN_MS).len(), 1);
+    assert!(debounce.filter(Vec::new(), 10_000, COOLDOWN_MS).is_empty());
+    assert!(debounce.filter(down(), 20_000, COOLDOWN_MS).is_empty(), "inside the cooldown");
+    assert!(debounce.filter(Vec::new(), 30_000, COOLDOWN_MS).is_empty());
+    assert_eq!(debounce.filter(down(), COOLDOWN_MS, COOLDOWN_MS).len(), 1, "cooldown passed");
+  }
+
+  #[test]
+  fn debounce_is_per_rule_and_container() {
+    let mut debounce = Debounce::default();
+    assert_eq!(
+      debounce
+        .filter(vec![alert(AlertRule::ContainerNotRunning, "fray-synapse")], 0, COOLDOWN_MS)
+        .len(),
+      1
+    );
+    let raised = debounce.filter(
+      vec![
+        alert(AlertRule::ContainerNotRunning, "fray-synapse"),
+        alert(AlertRule::ContainerNotRunning, "fray-postgres"),
+        alert(AlertRule::ContainerUnhealthy, "fray-synapse"),
+      ],
+      1_000,
+      COOLDOWN_MS,
+    );
+    assert_eq!(raised.len(), 2);
+  }
+}
@@ -0,0 +1,37 @@
+//! Secrets kept in the OS keychain (Secret Service on Linux, Keychain on
+//! macOS, Credential Manager on Windows).
+//!
+//! Every call may block on an unlock prompt, so commands should reach these
+//! from `spawn_blocking`.
+
+use crate::error::{CommandError, CommandResult, ErrorKind};
+
+fn keychain_error(context: &str, error: keyring::Error) -> CommandError {
+  CommandError::new(ErrorKind::Keychain, format!("{context}: {error}"))
+}
+
+fn entry(service: &str, account: &str) -> CommandResult<keyring::Entry> {
+  keyring::Entry::new(service, account).map_err(|error| keychain_error("Unable to open keychain entry", error))
+}
+
+/// Returns the stored secret, or `None` when there is no entry.
+pub fn read_secret(service: &str, account: &str) -> CommandResult<Option<String>> {
+  match entry(service, account)?.get_password() {
+    Ok(value) => Ok(Some(value)),
+    Err(keyring::Error::NoEntry) => Ok(None),
+    Err(error) => Err(keychain_error("Unable to read credential from the keychain", error)),
+  }
+}
+
+/// Stores `value`, or removes the entry when it is `None`.
+pub fn write_secret(service: &str, account: &str, value: Option<&str>) -> CommandResult<()> {
+  let entry = entry(service, account)?;
+  let result = match value {
+    Some(value) => entry.set_password(value),
+    None => match entry.delete_credential() {
+      Err(keyring::Error::NoEntry) => Ok(()),
+      other => other,
+    },
+  };
+  result.map_err(|error| keychain_error("Unable to update credential in the keychain", error))
+}
@@ -0,0 +1,270 @@
+//! Fray-managed SSH known hosts with trust-on-first-use.
+//!
+//! Host keys live in `known_hosts.json` in the app data dir, independent of
+//! `~/.ssh/known_hosts`. An unknown host fails with `host-key-unknown` and the
+//! presented fingerprint; once the user approves it through `trust_ssh_host`,
+//! any later change is a hard `host-key-mismatch`.
+
+use crate::app_data::{read_json_file, write_json_file};
+use crate::error::{CommandError, CommandResult, ErrorKind};
+use crate::now_millis;
+use crate::ssh::DEFAULT_SSH_PORT;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use tauri::State;
+
+pub const KNOWN_HOSTS_FILE: &str = "known_hosts.json";
+
+/// A host key as presented during the SSH handshake.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostKey {
+  pub host: String,
+  pub port: u16,
+  /// OpenSSH algorithm name, e.g. `ssh-ed25519`.
+  pub key_type: String,
+  /// `SHA256:<base64>`, the format `ssh-keygen -l` prints.
+  pub fingerprint: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustedHost {
+  #[serde(flatten)]
+  pub key: HostKey,
+  pub trusted_at: u64,
+}
+
+#[derive(Serialize)]
+struct HostKeyMismatchDetails<'a> {
+  #[serde(flatten)]
+  presented: &'a HostKey,
+  expected_key_type: &'a str,
+  expected_fingerprint: &'a str,
+}
+
+fn same_host(key: &HostKey, host: &str, port: u16) -> bool {
+  key.port == port && key.host.eq_ignore_ascii_case(host)
+}
+
+pub struct KnownHostsStore {
+  path: PathBuf,
+  hosts: Mutex<Vec<TrustedHost>>,
+}
+
+impl KnownHostsStore {
+  pub fn load(path: PathBuf) -> Self {
+    let hosts = read_json_file(&path);
+    Self {
+      path,
+      hosts: Mutex::new(hosts),
+    }
+  }
+
+  fn lock(&self) -> MutexGuard<'_, Vec<TrustedHost>> {
+    self.hosts.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+
+  /// Key type to request during negotiation so a server offering several
+  /// algorithms presents the one we trusted.
+  pub fn expected_key_type(&self, host: &str, port: u16) -> Option<String> {
+    self
+      .lock()
+      .iter()
+      .find(|trusted| same_host(&trusted.key, host, port))
+      .map(|trusted| trusted.key.key_type.clone())
+  }
+
+  pub fn verify(&self, presented: &HostKey) -> CommandResult<()> {
+    let hosts = self.lock();
+    let Some(trusted) = hosts
+      .iter()
+      .find(|trusted| same_host(&trusted.key, &presented.host, presented.port))
+    else {
+      return Err(
+        CommandError::new(
+          ErrorKind::HostKeyUnknown,
+          format!(
+            "The authenticity of {} can't be established. {} key fingerprint is {}.",
+            presented.host, presented.key_type, presented.fingerprint
+          ),
+        )
+        .with_details(presented),
+      );
+    };
+    if trusted.key.key_type == presented.key_type && trusted.key.fingerprint == presented.fingerprint {
+      return Ok(());
+    }
+    Err(
+      CommandError::new(
+        ErrorKind::HostKeyMismatch,
+        format!(
+          "Host key for {} has changed: expected {} but the server presented {}. Refusing to connect.",
+          presented.host, trusted.key.fingerprint, presented.fingerprint
+        ),
+      )
+      .with_details(HostKeyMismatchDetails {
+        presented,
+        expected_key_type: &trusted.key.key_type,
+        expected_fingerprint: &trusted.key.fingerprint,
+      }),
+    )
+  }
+
+  /// Trusts a key the user approved. Replacing a different trusted key
+  /// requires forgetting the host first, so a mismatch is never one click away
+  /// from being accepted.
+  fn trust(&self, key: HostKey) -> CommandResult<TrustedHost> {
+    let mut hosts = self.lock();
+    if let Some(existing) = hosts.iter().find(|trusted| same_host(&trusted.key, &key.host, key.port)) {
+      if existing.key.fingerprint == key.fingerprint && existing.key.key_type == key.key_type {
+        return Ok(existing.clone());
+      }
+      return Err(CommandError::invalid_input(format!(
+        "{} already has a different trusted key. Forget it before trusting a new one.",
+        key.host
+      )));
+    }
+    let trusted = TrustedHost {
+      key,
+      trusted_at: now_millis(),
+    };
+    hosts.push(trusted.clone());
+    write_json_file(&self.path, &*hosts);
+    Ok(trusted)
+  }
+
+  fn forget(&self, host: &str, port: u16) -> bool {
+    let mut hosts = self.lock();
+    let before = hosts.len();
+    hosts.retain(|trusted| !same_host(&trusted.key, host, port));
+    let removed = hosts.len() != before;
+    if removed {
+      write_json_file(&self.path, &*hosts);
+    }
+    removed
+  }
+
+  fn list(&self) -> Vec<TrustedHost> {
+    self.lock().clone()
+  }
+}
+
+#[tauri::command]
+pub fn trust_ssh_host(
+  store: State<'_, KnownHostsStore>,
+  host: String,
+  port: Option<u16>,
+  key_type: String,
+  fingerprint: String,
+) -> CommandResult<TrustedHost> {
+  let host = host.trim();
+  if host.is_empty() || !fingerprint.starts_with("SHA256:") {
+    return Err(CommandError::invalid_input(
+      "A host and the SHA256 fingerprint it presented are required.",
+    ));
+  }
+  store.trust(HostKey {
+    host: host.to_string(),
+    port: port.unwrap_or(DEFAULT_SSH_PORT),
+    key_type,
+    fingerprint,
+  })
+}
+
+#[tauri::command]
+pub fn list_trusted_ssh_hosts(store: State<'_, KnownHostsStore>) -> Vec<TrustedHost> {
+  store.list()
+}
+
+/// Returns whether a trusted key was removed.
+#[tauri::command]
+pub fn forget_ssh_host(store: State<'_, KnownHostsStore>, host: String, port: Option<u16>) -> bool {
+  store.forget(host.trim(), port.unwrap_or(DEFAULT_SSH_PORT))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn scratch_store(name: &str) -> KnownHostsStore {
+    let dir = std::env::temp_dir().join(format!("fray-{name}-{}-{}", std::process::id(), now_millis()));
+    KnownHostsStore::load(dir.join(KNOWN_HOSTS_FILE))
+  }
+
+  fn cleanup(store: &KnownHostsStore) {
+    let _ = std::fs::remove_dir_all(store.path.parent().unwrap());
+  }
+
+  fn key(fingerprint: &str) -> HostKey {
+    HostKey {
+      host: "matrix.example.com".to_string(),
+      port: DEFAULT_SSH_PORT,
+      key_type: "ssh-ed25519".to_string(),
+      fingerprint: fingerprint.to_string(),
+    }
+  }
+
+  #[test]
+  fn unknown_host_is_reported_with_its_fingerprint() {
+    let store = scratch_store("known-hosts-unknown");
+    let error = store.verify(&key("SHA256:first")).unwrap_err();
+    assert_eq!(error.kind, ErrorKind::HostKeyUnknown);
+    assert_eq!(error.details.unwrap()["fingerprint"], "SHA256:first");
+    cleanup(&store);
+  }
+
+  #[test]
+  fn trusted_key_verifies_case_insensitively_on_the_same_port() {
+    let store = scratch_store("known-hosts-trusted");
+    store.trust(key("SHA256:first")).unwrap();
+    let mut presented = key("SHA256:first");
+    presented.host = "Matrix.Example.COM".to_string();
+    store.verify(&presented).unwrap();
+
+    presented.port = 2222;
+    assert_eq!(store.verify(&presented).unwrap_err().kind, ErrorKind::HostKeyUnknown);
+    cleanup(&store);
+  }
+
+  #[test]
+  fn changed_key_is_a_mismatch() {
+    let store = scratch_store("known-hosts-mismatch");
+    store.trust(key("SHA256:first")).unwrap();
+    let error = store.verify(&key("SHA256:second")).unwrap_err();
+    assert_eq!(error.kind, ErrorKind::HostKeyMismatch);
+    let details = error.details.unwrap();
+    assert_eq!(details["fingerprint"], "SHA256:second");
+    assert_eq!(details["expected_fingerprint"], "SHA256:first");
+
+    let mut other_type = key("SHA256:first");
+    other_type.key_type = "ssh-rsa".to_string();
+    assert_eq!(store.verify(&other_type).unwrap_err().kind, ErrorKind::HostKeyMismatch);
+    cleanup(&store);
+  }
+
+  #[test]
+  fn trust_refuses_to_replace_a_different_key() {
+    let store = scratch_store("known-hosts-replace");
+    store.trust(key("SHA256:first")).unwrap();
+    store.trust(key("SHA256:first")).unwrap();
+    let error = store.trust(key("SHA256:second")).unwrap_err();
+    assert_eq!(error.kind, ErrorKind::InvalidInput);
+    assert_eq!(store.list().len(), 1);
+    assert_eq!(store.verify(&key("SHA256:second")).unwrap_err().kind, ErrorKind::HostKeyMismatch);
+    cleanup(&store);
+  }
+
+  #[test]
+  fn forget_then_trust_accepts_the_new_key() {
+    let store = scratch_store("known-hosts-forget");
+    store.trust(key("SHA256:first")).unwrap();
+    assert!(store.forget("MATRIX.example.com", DEFAULT_SSH_PORT));
+    assert!(!store.forget("matrix.example.com", DEFAULT_SSH_PORT));
+    store.trust(key("SHA256:second")).unwrap();
+    store.verify(&key("SHA256:second")).unwrap();
+
+    let reloaded = KnownHostsStore::load(store.path.clone());
+    reloaded.verify(&key("SHA256:second")).unwrap();
+    cleanup(&store);
+  }
+}
@@ -0,0 +1,525 @@
+//! Room hard-delete commands and the persisted registry of in-flight purges.
+//!
+//! Synapse keeps running a v2 delete after we stop polling it, so every purge
+//! that returns a `delete_id` is written to `purge_jobs.json` in the app data
+//! dir until it reaches a terminal state. Jobs left over from a previous run are
+//! re-attached on launch. The access token a job polls with is kept in the OS
+//! keychain under its `delete_id`, never in the JSON file.
+
+use crate::app_data::{read_json_file, write_json_file};
+use crate::error::{CommandError, CommandResult, ErrorKind};
+use crate::keychain;
+use crate::now_millis;
+use crate::server_profiles::admin_credentials;
+use crate::synapse_admin::rooms::{
+  DeleteObserver, DeleteProgress, DeleteRoomRequest, RoomDeleteOptions, ShutdownRoomResult,
+};
+use crate::synapse_admin::{normalize_base_url, SynapseAdminClient};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::Semaphore;
+
+pub const PURGE_JOBS_FILE: &str = "purge_jobs.json";
+const ROOM_DELETE_PROGRESS_EVENT: &str = "room-delete-progress";
+const KEYCHAIN_SERVICE: &str = "fray.purge-jobs";
+
+#[derive(Clone, Serialize)]
+struct RoomDeleteProgressEvent<'a> {
+  room_id: &'a str,
+  #[serde(flatten)]
+  progress: DeleteProgress,
+}
+
+fn emit_room_delete_progress(app: &AppHandle, room_id: &str, progress: DeleteProgress) {
+  let event = RoomDeleteProgressEvent { room_id, progress };
+  if let Err(error) = app.emit(ROOM_DELETE_PROGRESS_EVENT, event) {
+    log::warn!("Failed to emit room delete progress: {error}");
+  }
+}
+
+/// On-disk form of a purge job.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PurgeJobRecord {
+  room_id: String,
+  delete_id: String,
+  homeserver: String,
+  /// Plaintext token written by older versions; moved to the keychain on load.
+  #[serde(default, rename = "access_token", skip_serializing)]
+  legacy_access_token: Option<String>,
+  started_at: u64,
+  /// False for deletes without `purge`, where the room row is kept.
+  #[serde(default = "default_verify_removal")]
+  verify_removal: bool,
+}
+
+fn default_verify_removal() -> bool {
+  true
+}
+
+/// Stores or removes a job's access token on the blocking pool, once the
+/// job's previous keychain update (`after`) has landed so a quick store and
+/// delete cannot swap. Failures are logged: the job can still be resumed with
+/// a fresh token.
+fn update_job_token(
+  delete_id: String,
+  access_token: Option<String>,
+  after: Option<JoinHandle<()>>,
+) -> JoinHandle<()> {
+  tauri::async_runtime::spawn(async move {
+    if let Some(previous) = after {
+      let _ = previous.await;
+    }
+    let result = tauri::async_runtime::spawn_blocking(move || {
+      keychain::write_secret(KEYCHAIN_SERVICE, &delete_id, access_token.as_deref())
+        .map_err(|error| format!("Failed to update the access token for purge job {delete_id}: {error}"))
+    })
+    .await;
+    match result {
+      Ok(Ok(())) => {}
+      Ok(Err(message)) => log::warn!("{message}"),
+      Err(error) => log::warn!("Purge job token task failed: {error}"),
+    }
+  })
+}
+
+/// Picks the token to poll with: the profile token supplied on resume (stored
+/// for next time), otherwise the one saved when the job started.
+async fn job_access_token(delete_id: &str, supplied: Option<String>) -> CommandResult<String> {
+  let delete_id = delete_id.to_string();
+  let supplied = supplied.map(|token| token.trim().to_string()).filter(|token| !token.is_empty());
+  tauri::async_runtime::spawn_blocking(move || match supplied {
+    Some(token) => {
+      if let Err(error) = keychain::write_secret(KEYCHAIN_SERVICE, &delete_id, Some(&token)) {
+        log::warn!("Failed to update the access token for purge job {delete_id}: {error}");
+      }
+      Ok(token)
+    }
+    None => keychain::read_secret(KEYCHAIN_SERVICE, &delete_id)?.ok_or_else(|| {
+      CommandError::new(
+        ErrorKind::Auth,
+        "No access token is stored for this purge job. Resume it with a saved server profile.",
+      )
+    }),
+  })
+  .await
+  .map_err(|error| CommandError::internal(format!("Reading purge job token failed: {error}")))?
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PurgeJobSummary {
+  pub room_id: String,
+  pub delete_id: String,
+  pub homeserver: String,
+  pub started_at: u64,
+  /// Whether Fray is currently polling this job.
+  pub waiting: bool,
+}
+
+struct TrackedJob {
+  record: PurgeJobRecord,
+  cancelled: Arc<AtomicBool>,
+  waiting: bool,
+  /// Latest keychain update for the job's token, until someone waits on it.
+  token_update: Option<JoinHandle<()>>,
+}
+
+/// A detached job handed back to a poller by `begin_watch`.
+struct JobWatch {
+  record: PurgeJobRecord,
+  cancelled: Arc<AtomicBool>,
+  /// Pending token update to wait for before reading the token.
+  token_update: Option<JoinHandle<()>>,
+}
+
+pub struct PurgeJobStore {
+  path: PathBuf,
+  jobs: Mutex<HashMap<String, TrackedJob>>,
+}
+
+/// Errors that leave the purge possibly still running on the server; anything
+/// else means the job is finished one way or another.
+fn is_resumable(error: &CommandError) -> bool {
+  matches!(
+    error.kind,
+    ErrorKind::Cancelled | ErrorKind::Timeout | ErrorKind::Network | ErrorKind::Auth | ErrorKind::Keychain
+  )
+}
+
+impl PurgeJobStore {
+  pub fn load(path: PathBuf) -> Self {
+    let records: Vec<PurgeJobRecord> = read_json_file(&path);
+    let mut migrated = false;
+    let jobs = records
+      .into_iter()
+      .map(|mut record| {
+        let token_update = record.legacy_access_token.take().map(|access_token| {
+          migrated = true;
+          update_job_token(record.delete_id.clone(), Some(access_token), None)
+        });
+        let job = TrackedJob {
+          record,
+          cancelled: Arc::new(AtomicBool::new(false)),
+          waiting: false,
+          token_update,
+        };
+        (job.record.delete_id.clone(), job)
+      })
+      .collect();
+    let store = Self {
+      path,
+      jobs: Mutex::new(jobs),
+    };
+    if migrated {
+      store.persist(&store.lock());
+    }
+    store
+  }
+
+  fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, TrackedJob>> {
+    self.jobs.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+
+  fn persist(&self, jobs: &HashMap<String, TrackedJob>) {
+    let mut records: Vec<&PurgeJobRecord> = jobs.values().map(|job| &job.record).collect();
+    records.sort_by_key(|record| record.started_at);
+    write_json_file(&self.path, &records);
+  }
+
+  fn track(&self, record: PurgeJobRecord, access_token: &str, cancelled: Arc<AtomicBool>) {
+    let token_update = update_job_token(record.delete_id.clone(), Some(access_token.to_string()), None);
+    let mut jobs = self.lock();
+    jobs.insert(
+      record.delete_id.clone(),
+      TrackedJob {
+        record,
+        cancelled,
+        waiting: true,
+        token_update: Some(token_update),
+      },
+    );
+    self.persist(&jobs);
+  }
+
+  /// Marks a detached job as being polled again and hands back a fresh cancel flag.
+  fn begin_watch(&self, delete_id: &str) -> CommandResult<Option<JobWatch>> {
+    let mut jobs = self.lock();
+    let job = jobs
+      .get_mut(delete_id)
+      .ok_or_else(|| CommandError::new(ErrorKind::NotFound, format!("Unknown purge job {delete_id}.")))?;
+    if job.waiting {
+      return Ok(None);
+    }
+    job.waiting = true;
+    job.cancelled = Arc::new(AtomicBool::new(false));
+    Ok(Some(JobWatch {
+      record: job.record.clone(),
+      cancelled: job.cancelled.clone(),
+      token_update: job.token_update.take(),
+    }))
+  }
+
+  fn finish<T>(&self, delete_id: &str, result: &CommandResult<T>) {
+    let removed = {
+      let mut jobs = self.lock();
+      match result {
+        Err(error) if is_resumable(error) => {
+          if let Some(job) = jobs.get_mut(delete_id) {
+            job.waiting = false;
+          }
+          None
+        }
+        _ => {
+          let removed = jobs.remove(delete_id);
+          if removed.is_some() {
+            self.persist(&jobs);
+          }
+          removed
+        }
+      }
+    };
+    if let Some(job) = removed {
+      update_job_token(delete_id.to_string(), None, job.token_update);
+    }
+  }
+
+  fn homeserver(&self, delete_id: &str) -> CommandResult<String> {
+    self
+      .lock()
+      .get(delete_id)
+      .map(|job| job.record.homeserver.clone())
+      .ok_or_else(|| CommandError::new(ErrorKind::NotFound, format!("Unknown purge job {delete_id}.")))
+  }
+
+  fn cancel(&self, delete_id: &str) -> CommandResult<()> {
+    let jobs = self.lock();
+    let job = jobs
+      .get(delete_id)
+      .ok_or_else(|| CommandError::new(ErrorKind::NotFound, format!("Unknown purge job {delete_id}.")))?;
+    job.cancelled.store(true, Ordering::SeqCst);
+    Ok(())
+  }
+
+  fn forget(&self, delete_id: &str) {
+    let removed = {
+      let mut jobs = self.lock();
+      let removed = jobs.remove(delete_id);
+      if let Some(job) = &removed {
+        job.cancelled.store(true, Ordering::SeqCst);
+        self.persist(&jobs);
+      }
+      removed
+    };
+    if let Some(job) = removed {
+      update_job_token(delete_id.to_string(), None, job.token_update);
+    }
+  }
+
+  fn list(&self) -> Vec<PurgeJobSummary> {
+    let jobs = self.lock();
+    let mut summaries: Vec<PurgeJobSummary> = jobs
+      .values()
+      .map(|job| PurgeJobSummary {
+        room_id: job.record.room_id.clone(),
+        delete_id: job.record.delete_id.clone(),
+        homeserver: job.record.homeserver.clone(),
+        started_at: job.record.started_at,
+        waiting: job.waiting,
+      })
+      .collect();
+    summaries.sort_by_key(|summary| summary.started_at);
+    summaries
+  }
+}
+
+fn spawn_watch(app: AppHandle, watch: JobWatch, access_token: Option<String>) {
+  let JobWatch {
+    record,
+    cancelled,
+    token_update,
+  } = watch;
+  tauri::async_runtime::spawn(async move {
+    if let Some(token_update) = token_update {
+      let _ = token_update.await;
+    }
+    let on_progress = |progress| emit_room_delete_progress(&app, &record.room_id, progress);
+    let observer = DeleteObserver {
+      on_progress: &on_progress,
+      cancelled: &cancelled,
+    };
+    let result = match job_access_token(&record.delete_id, access_token).await {
+      Ok(access_token) => {
+        SynapseAdminClient::new(&record.homeserver, &access_token)
+          .watch_delete(&record.room_id, &record.delete_id, record.verify_removal, observer)
+          .await
+      }
+      Err(error) => {
+        on_progress(DeleteProgress::Failed { error: error.clone() });
+        Err(error)
+      }
+    };
+    app.state::<PurgeJobStore>().finish(&record.delete_id, &result);
+  });
+}
+
+/// Re-attaches to every job persisted by a previous run.
+pub fn resume_all(app: &AppHandle) {
+  let store = app.state::<PurgeJobStore>();
+  let delete_ids: Vec<String> = store.list().into_iter().map(|job| job.delete_id).collect();
+  for delete_id in delete_ids {
+    if let Ok(Some(watch)) = store.begin_watch(&delete_id) {
+      spawn_watch(app.clone(), watch, None);
+    }
+  }
+}
+
+/// Runs one hard delete, registering its `delete_id` with the job store as soon
+/// as Synapse accepts it and emitting progress for `room_id`.
+async fn delete_room_tracked(
+  app: &AppHandle,
+  base_url: &str,
+  access_token: &str,
+  room_id: &str,
+  request: &DeleteRoomRequest,
+) -> CommandResult<ShutdownRoomResult> {
+  let store = app.state::<PurgeJobStore>();
+  let client = SynapseAdminClient::new(base_url, access_token);
+  let cancelled = Arc::new(AtomicBool::new(false));
+  let tracked_delete_id = OnceLock::new();
+  let on_progress = |progress: DeleteProgress| {
+    if let DeleteProgress::Accepted {
+      delete_id: Some(delete_id),
+      ..
+    } = &progress
+    {
+      if tracked_delete_id.set(delete_id.clone()).is_ok() {
+        store.track(
+          PurgeJobRecord {
+            room_id: room_id.to_string(),
+            delete_id: delete_id.clone(),
+            homeserver: base_url.to_string(),
+            legacy_access_token: None,
+            started_at: now_millis(),
+            verify_removal: request.purge,
+          },
+          access_token,
+          cancelled.clone(),
+        );
+      }
+    }
+    emit_room_delete_progress(app, room_id, progress);
+  };
+  let observer = DeleteObserver {
+    on_progress: &on_progress,
+    cancelled: &cancelled,
+  };
+
+  let result = client.hard_delete_room(room_id, request, observer).await;
+  if let Some(delete_id) = tracked_delete_id.get() {
+    store.finish(delete_id, &result);
+  }
+  result
+}
+
+#[tauri::command]
+pub async fn synapse_hard_delete_room(
+  app: AppHandle,
+  profile_id: String,
+  room_id: String,
+  requester_user_id: String,
+  options: Option<RoomDeleteOptions>,
+) -> CommandResult<ShutdownRoomResult> {
+  let request = DeleteRoomRequest::from_options(options.unwrap_or_default(), requester_user_id)?;
+  let (base_url, access_token) = admin_credentials(&app, &profile_id).await?;
+  delete_room_tracked(&app, &base_url, &access_token, &room_id, &request).await
+}
+
+const DEFAULT_BULK_DELETE_CONCURRENCY: usize = 3;
+const MAX_BULK_DELETE_CONCURRENCY: usize = 8;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoomDeleteOutcome {
+  pub room_id: String,
+  pub ok: bool,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub result: Option<ShutdownRoomResult>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub error: Option<CommandError>,
+}
+
+/// Deletes many rooms with at most `concurrency` purges in flight. When
+/// `space_id` is given its descendants are added to `room_ids`; the space room
+/// itself is only deleted when `include_space` is set, after its children.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn synapse_hard_delete_rooms(
+  app: AppHandle,
+  profile_id: String,
+  requester_user_id: String,
+  room_ids: Option<Vec<String>>,
+  space_id: Option<String>,
+  include_space: Option<bool>,
+  concurrency: Option<usize>,
+  options: Option<RoomDeleteOptions>,
+) -> CommandResult<Vec<RoomDeleteOutcome>> {
+  let request = DeleteRoomRequest::from_options(options.unwrap_or_default(), requester_user_id)?;
+  let (base_url, access_token) = admin_credentials(&app, &profile_id).await?;
+  let mut targets = room_ids.unwrap_or_default();
+  if let Some(space_id) = space_id.as_deref().map(str::trim).filter(|id| !id.is_empty()) {
+    let client = SynapseAdminClient::new(&base_url, &access_token);
+    targets.extend(client.space_descendants(space_id).await?);
+    if include_space.unwrap_or(false) {
+      targets.push(space_id.to_string());
+    }
+  }
+  let mut seen = HashSet::new();
+  targets.retain(|room_id| !room_id.trim().is_empty() && seen.insert(room_id.clone()));
+  if targets.is_empty() {
+    return Err(CommandError::invalid_input("No rooms to delete."));
+  }
+
+  let limit = concurrency
+    .unwrap_or(DEFAULT_BULK_DELETE_CONCURRENCY)
+    .clamp(1, MAX_BULK_DELETE_CONCURRENCY);
+  let semaphore = Arc::new(Semaphore::new(limit));
+
+  let handles: Vec<_> = targets
+    .into_iter()
+    .map(|room_id| {
+      let app = app.clone();
+      let semaphore = semaphore.clone();
+      let base_url = base_url.clone();
+      let access_token = access_token.clone();
+      let request = request.clone();
+      tauri::async_runtime::spawn(async move {
+        let result = match semaphore.acquire_owned().await {
+          Ok(_permit) => delete_room_tracked(&app, &base_url, &access_token, &room_id, &request).await,
+          Err(_) => Err(CommandError::internal("Bulk delete was shut down.")),
+        };
+        match result {
+          Ok(shutdown) => RoomDeleteOutcome {
+            room_id,
+            ok: true,
+            result: Some(shutdown),
+            error: None,
+          },
+          Err(error) => RoomDeleteOutcome {
+            room_id,
+            ok: false,
+            result: None,
+            error: Some(error),
+          },
+        }
+      })
+    })
+    .collect();
+
+  let mut outcomes = Vec::with_capacity(handles.len());
+  for handle in handles {
+    outcomes.push(
+      handle
+        .await
+        .map_err(|error| CommandError::internal(format!("Room delete task failed: {error}")))?,
+    );
+  }
+  Ok(outcomes)
+}
+
+#[tauri::command]
+pub fn list_purge_jobs(store: State<'_, PurgeJobStore>) -> Vec<PurgeJobSummary> {
+  store.list()
+}
+
+/// Stops polling a purge. Synapse keeps deleting the room; the job stays listed
+/// so it can be resumed later.
+#[tauri::command]
+pub fn cancel_purge_job(store: State<'_, PurgeJobStore>, delete_id: String) -> CommandResult<()> {
+  store.cancel(&delete_id)
+}
+
+/// Polls a detached job again with the admin token of a saved profile, which
+/// replaces the stored token, e.g. after the one the job started with was
+/// revoked. The profile must point at the job's homeserver.
+#[tauri::command]
+pub async fn resume_purge_job(app: AppHandle, profile_id: String, delete_id: String) -> CommandResult<()> {
+  let (homeserver, access_token) = admin_credentials(&app, &profile_id).await?;
+  let store = app.state::<PurgeJobStore>();
+  if normalize_base_url(&store.homeserver(&delete_id)?) != homeserver {
+    return Err(CommandError::invalid_input(
+      "This server profile is for a different homeserver than the purge job.",
+    ));
+  }
+  if let Some(watch) = store.begin_watch(&delete_id)? {
+    spawn_watch(app.clone(), watch, Some(access_token));
+  }
+  Ok(())
+}
+
+#[tauri::command]
+pub fn forget_purge_job(store: State<'_, PurgeJobStore>, delete_id: String) {
+  store.forget(&delete_id);
+}
@@ -0,0 +1,524 @@
+//! Remote server diagnostics collected over SSH.
+//!
+//! The host is read with plain POSIX tools (`/proc`, `df`) plus `docker` and
+//! `psql` inside the containers, so nothing beyond a shell and Docker is
+//! needed on the server. Output is parsed natively by [`parse`].
+
+mod parse;
+
+use crate::error::{CommandError, CommandResult, ErrorKind};
+use crate::health_history::HealthHistoryStore;
+use crate::now_millis;
+use crate::server_profiles::open_session;
+use crate::ssh::{shell_escape, RemoteShell};
+use parse::{
+  cpu_percent, parse_cpu_sample, parse_database_counts, parse_df, parse_docker_inspect,
+  parse_docker_stats, parse_loadavg, parse_meminfo, parse_uptime, split_sections, ContainerState,
+  ContainerStats, CpuSample, DATABASE_QUERY, INSPECT_FORMAT, SECTION_MARKER,
+};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Container and database names the health check inspects.
+#[derive(Debug, Clone)]
+pub struct HealthTargets {
+  pub synapse_container: String,
+  pub postgres_container: String,
+  pub postgres_user: String,
+  pub postgres_db: String,
+}
+
+impl HealthTargets {
+  pub fn new(
+    synapse_container: Option<String>,
+    postgres_container: Option<String>,
+    postgres_user: Option<String>,
+    postgres_db: Option<String>,
+  ) -> Self {
+    let or_default = |value: Option<String>, default: &str| {
+      value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| default.to_string())
+    };
+    Self {
+      synapse_container: or_default(synapse_container, "fray-synapse"),
+      postgres_container: or_default(postgres_container, "fray-postgres"),
+      postgres_user: or_default(postgres_user, "synapse"),
+      postgres_db: or_default(postgres_db, "synapse"),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HostHealth {
+  pub cpu_percent: f64,
+  pub load_1m: f64,
+  pub load_5m: f64,
+  pub load_15m: f64,
+  pub uptime_seconds: u64,
+  pub memory_total_bytes: u64,
+  pub memory_used_bytes: u64,
+  pub memory_available_bytes: u64,
+  pub disk_total_bytes: u64,
+  pub disk_used_bytes: u64,
+  pub disk_available_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MatrixHealth {
+  pub container: String,
+  pub status: String,
+  pub health: String,
+  pub version: Option<String>,
+  pub room_count: Option<u64>,
+  pub user_count: Option<u64>,
+  pub joined_memberships: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DatabaseHealth {
+  pub container: String,
+  pub status: String,
+  pub health: String,
+  pub database: String,
+  pub size_bytes: Option<u64>,
+  pub active_connections: Option<u64>,
+}
+
+/// Per-container row. Keys are camelCase, as the frontend has always read them.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContainerHealth {
+  pub name: String,
+  pub status: String,
+  pub health: String,
+  pub cpu_percent: Option<String>,
+  pub memory_percent: Option<String>,
+  pub memory_usage: Option<String>,
+  pub network_io: Option<String>,
+  pub block_io: Option<String>,
+  pub pids: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerHealthSnapshot {
+  pub captured_at: u64,
+  pub host: HostHealth,
+  pub matrix: MatrixHealth,
+  pub database: DatabaseHealth,
+  pub containers: Vec<ContainerHealth>,
+  /// Non-fatal problems; the matching fields are left empty.
+  pub errors: Vec<String>,
+}
+
+const UNKNOWN: &str = "unknown";
+/// Pause before re-sampling `/proc/stat` when no CPU time elapsed between samples.
+const CPU_RESAMPLE_DELAY: Duration = Duration::from_millis(250);
+
+/// Reads the host files in one round trip. `df -P` keeps each mount on one line.
+fn host_command() -> String {
+  ["head -n 1 /proc/stat", "cat /proc/meminfo", "cat /proc/loadavg", "cat /proc/uptime", "df -P -k /"]
+    .join(&format!("; echo '{SECTION_MARKER}'; "))
+}
+
+fn first_line(text: &str) -> &str {
+  text.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or("unknown error")
+}
+
+fn read_cpu_sample(shell: &dyn RemoteShell) -> Option<CpuSample> {
+  shell
+    .run("head -n 1 /proc/stat")
+    .ok()
+    .and_then(|output| parse_cpu_sample(&output.stdout))
+}
+
+fn round2(value: f64) -> f64 {
+  (value * 100.0).round() / 100.0
+}
+
+/// Collects a snapshot by running plain POSIX and docker commands over
+/// `shell` and parsing their output. Only a failure to read the host's
+/// `/proc` files is fatal; everything else is reported in `errors`.
+pub fn collect_health(shell: &dyn RemoteShell, targets: &HealthTargets) -> CommandResult<ServerHealthSnapshot> {
+  let mut errors = Vec::new();
+
+  let output = shell.run(&host_command())?;
+  let sections = split_sections(&output.stdout);
+  let section = |index: usize| sections.get(index).map(String::as_str).unwrap_or_default();
+  let first_cpu = parse_cpu_sample(section(0)).ok_or_else(|| {
+    let stderr = output.stderr.trim();
+    CommandError::new(
+      ErrorKind::Ssh,
+      if stderr.is_empty() {
+        "Unable to read /proc/stat on the remote host.".to_string()
+      } else {
+        format!("Unable to read /proc/stat on the remote host: {}", first_line(stderr))
+      },
+    )
+  })?;
+  let mut host = HostHealth::default();
+  match parse_meminfo(section(1)) {
+    Some(memory) => {
+      host.memory_total_bytes = memory.total_bytes;
+      host.memory_used_bytes = memory.used_bytes();
+      host.memory_available_bytes = memory.available_bytes;
+    }
+    None => errors.push("Unable to parse /proc/meminfo.".to_string()),
+  }
+  match parse_loadavg(section(2)) {
+    Some((one, five, fifteen)) => {
+      host.load_1m = round2(one);
+      host.load_5m = round2(five);
+      host.load_15m = round2(fifteen);
+    }
+    None => errors.push("Unable to parse /proc/loadavg.".to_string()),
+  }
+  match parse_uptime(section(3)) {
+    Some(uptime) => host.uptime_seconds = uptime,
+    None => errors.push("Unable to parse /proc/uptime.".to_string()),
+  }
+  match parse_df(section(4)) {
+    Some(disk) => {
+      host.disk_total_bytes = disk.total_bytes;
+      host.disk_used_bytes = disk.used_bytes;
+      host.disk_available_bytes = disk.available_bytes;
+    }
+    None => errors.push("Unable to parse df output.".to_string()),
+  }
+
+  let stats_output = shell.run("docker stats --no-stream --format '{{json .}}'")?;
+  let stats = if stats_output.exit_status == 0 {
+    parse_docker_stats(&stats_output.stdout)
+  } else {
+    errors.push(format!("docker stats failed: {}", first_line(&stats_output.stderr)));
+    Vec::new()
+  };
+
+  let mut names: BTreeSet<String> = stats.iter().map(|entry| entry.name.trim().to_string()).collect();
+  names.insert(targets.synapse_container.clone());
+  names.insert(targets.postgres_container.clone());
+  let escaped_names: Vec<String> = names.iter().map(|name| shell_escape(name)).collect();
+  let inspect_output = shell.run(&format!(
+    "docker inspect -f {} {}",
+    shell_escape(INSPECT_FORMAT),
+    escaped_names.join(" ")
+  ))?;
+  // Targets may name a container or give (a prefix of) its id, so states are
+  // keyed by both.
+  let mut states: HashMap<String, ContainerState> = HashMap::new();
+  for state in parse_docker_inspect(&inspect_output.stdout) {
+    states.insert(state.id.clone(), state.clone());
+    states.insert(state.name.clone(), state);
+  }
+  let find_state = |requested: &str| {
+    states
+      .get(requested)
+      .or_else(|| states.values().find(|state| state.id.starts_with(requested)))
+  };
+  let state_of = |requested: &str| {
+    find_state(requested).cloned().unwrap_or_else(|| ContainerState {
+      id: String::new(),
+      name: requested.to_string(),
+      status: UNKNOWN.to_string(),
+      health: UNKNOWN.to_string(),
+    })
+  };
+  // One row per container, under its real name.
+  let mut rows = BTreeSet::new();
+  for name in &names {
+    match find_state(name) {
+      Some(state) => rows.insert(state.name.clone()),
+      None => {
+        errors.push(format!("{name} inspect failed: {}", first_line(&inspect_output.stderr)));
+        rows.insert(name.clone())
+      }
+    };
+  }
+  let stats_by_name: HashMap<&str, &ContainerStats> =
+    stats.iter().map(|entry| (entry.name.trim(), entry)).collect();
+  let containers = rows
+    .iter()
+    .map(|name| {
+      let state = state_of(name);
+      let stats = stats_by_name.get(name.as_str()).map(|stats| (*stats).clone()).unwrap_or_default();
+      ContainerHealth {
+        name: name.clone(),
+        status: state.status,
+        health: state.health,
+        cpu_percent: stats.cpu_percent,
+        memory_percent: stats.memory_percent,
+        memory_usage: stats.memory_usage,
+        network_io: stats.network_io,
+        block_io: stats.block_io,
+        pids: stats.pids,
+      }
+    })
+    .collect();
+
+  let version_output = shell.run(&format!(
+    "docker exec {} python -c 'import synapse; print(synapse.__version__)'",
+    shell_escape(&targets.synapse_container)
+  ))?;
+  let version = if version_output.exit_status == 0 {
+    Some(version_output.stdout.trim().to_string()).filter(|version| !version.is_empty())
+  } else {
+    errors.push(format!("Synapse version query failed: {}", first_line(&version_output.stderr)));
+    None
+  };
+
+  let database_output = shell.run(&format!(
+    "docker exec {} psql -U {} -d {} -At -F '|' -c {}",
+    shell_escape(&targets.postgres_container),
+    shell_escape(&targets.postgres_user),
+    shell_escape(&targets.postgres_db),
+    shell_escape(DATABASE_QUERY)
+  ))?;
+  let counts = if database_output.exit_status == 0 {
+    parse_database_counts(&database_output.stdout)
+      .map_err(|error| errors.push(error.to_string()))
+      .ok()
+  } else {
+    errors.push(format!("PostgreSQL health query failed: {}", first_line(&database_output.stderr)));
+    None
+  };
+
+  // The commands above took real time, so the two samples bracket a useful interval.
+  let mut second_cpu = read_cpu_sample(shell);
+  if second_cpu.is_some_and(|second| second.total == first_cpu.total) {
+    std::thread::sleep(CPU_RESAMPLE_DELAY);
+    second_cpu = read_cpu_sample(shell);
+  }
+  match second_cpu.and_then(|second| cpu_percent(first_cpu, second)) {
+    Some(percent) => host.cpu_percent = round2(percent),
+    None => errors.push("Unable to sample CPU usage.".to_string()),
+  }
+
+  let synapse = state_of(&targets.synapse_container);
+  let postgres = state_of(&targets.postgres_container);
+  Ok(ServerHealthSnapshot {
+    captured_at: now_millis(),
+    host,
+    matrix: MatrixHealth {
+      container: synapse.name,
+      status: synapse.status,
+      health: synapse.health,
+      version,
+      room_count: counts.map(|counts| counts.room_count),
+      user_count: counts.map(|counts| counts.user_count),
+      joined_memberships: counts.map(|counts| counts.joined_memberships),
+    },
+    database: DatabaseHealth {
+      container: postgres.name,
+      status: postgres.status,
+      health: postgres.health,
+      database: targets.postgres_db.clone(),
+      size_bytes: counts.map(|counts| counts.size_bytes),
+      active_connections: counts.map(|counts| counts.active_connections),
+    },
+    containers,
+    errors,
+  })
+}
+
+/// Collects a snapshot for a saved profile and adds it to the profile's
+/// history. Blocking; shared by the command and the background monitor.
+pub fn poll_profile_health(app: &AppHandle, profile_id: &str) -> CommandResult<ServerHealthSnapshot> {
+  let (profile, session) = open_session(app, profile_id)?;
+  let snapshot = collect_health(&session, &profile.health_targets())?;
+  if let Err(error) = app.state::<HealthHistoryStore>().record(&profile.id, &snapshot) {
+    log::warn!("Failed to record health snapshot for {}: {error}", profile.id);
+  }
+  Ok(snapshot)
+}
+
+/// Collects a health snapshot over SSH for a saved server profile.
+#[tauri::command]
+pub async fn fetch_remote_server_health(
+  app: AppHandle,
+  profile_id: String,
+) -> CommandResult<ServerHealthSnapshot> {
+  tauri::async_runtime::spawn_blocking(move || poll_profile_health(&app, &profile_id))
+    .await
+    .map_err(|error| CommandError::internal(format!("Server health task failed: {error}")))?
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::ssh::RemoteOutput;
+  use std::cell::{Cell, RefCell};
+
+  const HOST_OUTPUT: &str = "cpu  100 0 100 700 100 0 0 0 0 0
+--fray-section--
+MemTotal:        2048 kB
+MemFree:          512 kB
+MemAvailable:    1024 kB
+--fray-section--
+0.504 0.25 0.10 1/123 4567
+--fray-section--
+3600.55 7000.00
+--fray-section--
+Filesystem     1024-blocks  Used Available Capacity Mounted on
+/dev/sda1             1000   600       300      67% /
+";
+
+  /// Stand-in for an SSH session that answers each command from fixtures.
+  struct FakeShell {
+    docker_available: bool,
+    cpu_reads: Cell<usize>,
+    commands: RefCell<Vec<String>>,
+  }
+
+  impl FakeShell {
+    fn new(docker_available: bool) -> Self {
+      Self {
+        docker_available,
+        cpu_reads: Cell::new(0),
+        commands: RefCell::new(Vec::new()),
+      }
+    }
+  }
+
+  fn ok(stdout: &str) -> RemoteOutput {
+    RemoteOutput {
+      exit_status: 0,
+      stdout: stdout.to_string(),
+      stderr: String::new(),
+    }
+  }
+
+  fn failed(stderr: &str) -> RemoteOutput {
+    RemoteOutput {
+      exit_status: 1,
+      stdout: String::new(),
+      stderr: stderr.to_string(),
+    }
+  }
+
+  impl RemoteShell for FakeShell {
+    fn run(&self, command: &str) -> CommandResult<RemoteOutput> {
+      self.commands.borrow_mut().push(command.to_string());
+      if command.starts_with("head -n 1 /proc/stat;") {
+        return Ok(ok(HOST_OUTPUT));
+      }
+      if command == "head -n 1 /proc/stat" {
+        self.cpu_reads.set(self.cpu_reads.get() + 1);
+        return Ok(ok("cpu  200 0 150 750 100 0 0 0 0 0\n"));
+      }
+      if !self.docker_available {
+        return Ok(failed("Cannot connect to the Docker daemon at unix:///var/run/docker.sock.\n"));
+      }
+      if command.starts_with("docker stats") {
+        return Ok(ok(concat!(
+          r#"{"Name":"fray-synapse","CPUPerc":"1.50%","MemPerc":"12.00%","MemUsage":"240MiB / 2GiB","NetIO":"1kB / 2kB","BlockIO":"0B / 0B","PIDs":"42"}"#,
+          "\n",
+          r#"{"Name":"fray-postgres","CPUPerc":"0.20%","MemPerc":"4.00%","MemUsage":"80MiB / 2GiB","NetIO":"3kB / 4kB","BlockIO":"0B / 0B","PIDs":"9"}"#,
+          "\n",
+        )));
+      }
+      if command.starts_with("docker inspect") {
+        return Ok(ok(concat!(
+          "0f1e2d3c4b5a|/fray-postgres|running|none\n",
+          "a1b2c3d4e5f6|/fray-synapse|running|healthy\n",
+        )));
+      }
+      if command.contains("synapse.__version__") {
+        return Ok(ok("1.98.0\n"));
+      }
+      if command.contains("psql") {
+        return Ok(ok("1048576|3|10|20|40\n"));
+      }
+      Ok(failed("unexpected command"))
+    }
+  }
+
+  fn targets() -> HealthTargets {
+    HealthTargets::new(None, None, None, None)
+  }
+
+  #[test]
+  fn collects_snapshot_from_command_output() {
+    let shell = FakeShell::new(true);
+    let snapshot = collect_health(&shell, &targets()).expect("snapshot");
+
+    assert!(snapshot.errors.is_empty(), "unexpected errors: {:?}", snapshot.errors);
+    assert_eq!(snapshot.host.cpu_percent, 75.0);
+    assert_eq!(snapshot.host.load_1m, 0.5);
+    assert_eq!(snapshot.host.uptime_seconds, 3600);
+    assert_eq!(snapshot.host.memory_total_bytes, 2048 * 1024);
+    assert_eq!(snapshot.host.memory_used_bytes, 1024 * 1024);
+    assert_eq!(snapshot.host.disk_total_bytes, 1000 * 1024);
+    assert_eq!(snapshot.host.disk_used_bytes, 700 * 1024);
+
+    assert_eq!(snapshot.matrix.status, "running");
+    assert_eq!(snapshot.matrix.health, "healthy");
+    assert_eq!(snapshot.matrix.version.as_deref(), Some("1.98.0"));
+    assert_eq!(snapshot.matrix.room_count, Some(10));
+    assert_eq!(snapshot.matrix.joined_memberships, Some(40));
+    assert_eq!(snapshot.database.size_bytes, Some(1_048_576));
+    assert_eq!(snapshot.database.active_connections, Some(3));
+
+    let names: Vec<&str> = snapshot.containers.iter().map(|container| container.name.as_str()).collect();
+    assert_eq!(names, ["fray-postgres", "fray-synapse"]);
+    assert_eq!(snapshot.containers[1].pids.as_deref(), Some("42"));
+    assert_eq!(shell.cpu_reads.get(), 1);
+  }
+
+  #[test]
+  fn finds_containers_configured_by_id() {
+    let shell = FakeShell::new(true);
+    let targets = HealthTargets::new(Some("a1b2c3".into()), Some("0f1e2d3c4b5a".into()), None, None);
+    let snapshot = collect_health(&shell, &targets).expect("snapshot");
+
+    assert!(snapshot.errors.is_empty(), "unexpected errors: {:?}", snapshot.errors);
+    assert_eq!(snapshot.matrix.container, "fray-synapse");
+    assert_eq!(snapshot.matrix.health, "healthy");
+    assert_eq!(snapshot.database.container, "fray-postgres");
+    assert_eq!(snapshot.database.status, "running");
+    let names: Vec<&str> = snapshot.containers.iter().map(|container| container.name.as_str()).collect();
+    assert_eq!(names, ["fray-postgres", "fray-synapse"]);
+  }
+
+  #[test]
+  fn escapes_container_names_in_commands() {
+    let shell = FakeShell::new(true);
+    let targets = HealthTargets::new(Some("synapse; rm -rf /".into()), None, None, None);
+    collect_health(&shell, &targets).expect("snapshot");
+    let commands = shell.commands.borrow();
+    assert!(commands
+      .iter()
+      .any(|command| command.starts_with("docker exec 'synapse; rm -rf /' python")));
+  }
+
+  #[test]
+  fn docker_failures_are_reported_not_fatal() {
+    let shell = FakeShell::new(false);
+    let snapshot = collect_health(&shell, &targets()).expect("snapshot");
+
+    assert_eq!(snapshot.host.cpu_percent, 75.0);
+    assert_eq!(snapshot.matrix.status, UNKNOWN);
+    assert!(snapshot.matrix.version.is_none());
+    assert!(snapshot.database.size_bytes.is_none());
+    assert!(snapshot
+      .errors
+      .iter()
+      .any(|error| error.starts_with("docker stats failed: Cannot connect to the Docker daemon")));
+    assert!(snapshot.errors.iter().any(|error| error.starts_with("fray-synapse inspect failed")));
+  }
+
+  #[test]
+  fn unreadable_proc_is_fatal() {
+    struct NoProc;
+    impl RemoteShell for NoProc {
+      fn run(&self, _command: &str) -> CommandResult<RemoteOutput> {
+        Ok(failed("head: cannot open '/proc/stat' for reading: No such file or directory\n"))
+      }
+    }
+    let error = collect_health(&NoProc, &targets()).unwrap_err();
+    assert_eq!(error.kind, ErrorKind::Ssh);
+    assert!(error.message.ends_with("No such file or directory"));
+  }
+}
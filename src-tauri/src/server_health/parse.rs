@@ -0,0 +1,375 @@
+//! Parsers for the raw output of the remote health commands.
+//!
+//! Each parser takes the exact stdout of one command, so it can be exercised
+//! against captured fixture output without an SSH session.
+
+use serde::Deserialize;
+
+/// Line printed between the outputs of a batched command.
+pub const SECTION_MARKER: &str = "--fray-section--";
+
+/// Splits batched output on [`SECTION_MARKER`] lines. A command that printed
+/// nothing yields an empty section, so indices stay aligned.
+pub fn split_sections(output: &str) -> Vec<String> {
+  let mut sections = vec![String::new()];
+  for line in output.lines() {
+    if line.trim() == SECTION_MARKER {
+      sections.push(String::new());
+    } else if let Some(section) = sections.last_mut() {
+      section.push_str(line);
+      section.push('\n');
+    }
+  }
+  sections
+}
+
+/// Aggregate CPU jiffies from the first line of `/proc/stat`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuSample {
+  pub total: u64,
+  pub idle: u64,
+}
+
+/// Parses the `cpu` line of `/proc/stat`, counting user through softirq and
+/// treating idle plus iowait as idle time.
+pub fn parse_cpu_sample(stat: &str) -> Option<CpuSample> {
+  let line = stat.lines().find(|line| line.starts_with("cpu "))?;
+  let values = line
+    .split_whitespace()
+    .skip(1)
+    .take(7)
+    .map(str::parse::<u64>)
+    .collect::<Result<Vec<_>, _>>()
+    .ok()?;
+  if values.len() < 5 {
+    return None;
+  }
+  Some(CpuSample {
+    total: values.iter().sum(),
+    idle: values[3] + values[4],
+  })
+}
+
+/// Busy share between two samples, clamped to `0..=100`. `None` when no time
+/// passed between them.
+pub fn cpu_percent(first: CpuSample, second: CpuSample) -> Option<f64> {
+  let total = second.total.checked_sub(first.total).filter(|total| *total > 0)?;
+  let idle = second.idle.saturating_sub(first.idle).min(total);
+  Some(((total - idle) as f64 * 100.0 / total as f64).clamp(0.0, 100.0))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryUsage {
+  pub total_bytes: u64,
+  pub available_bytes: u64,
+}
+
+impl MemoryUsage {
+  pub fn used_bytes(&self) -> u64 {
+    self.total_bytes.saturating_sub(self.available_bytes)
+  }
+}
+
+/// Parses `/proc/meminfo`. Kernels older than 3.14 lack `MemAvailable`, so
+/// free plus page cache stands in for it there.
+pub fn parse_meminfo(meminfo: &str) -> Option<MemoryUsage> {
+  let field = |name: &str| {
+    meminfo.lines().find_map(|line| {
+      let (key, rest) = line.split_once(':')?;
+      if key.trim() != name {
+        return None;
+      }
+      rest.split_whitespace().next()?.parse::<u64>().ok().map(|kib| kib * 1024)
+    })
+  };
+  let total_bytes = field("MemTotal")?;
+  let available_bytes = field("MemAvailable").unwrap_or_else(|| {
+    field("MemFree").unwrap_or(0) + field("Buffers").unwrap_or(0) + field("Cached").unwrap_or(0)
+  });
+  Some(MemoryUsage {
+    total_bytes,
+    available_bytes,
+  })
+}
+
+/// Parses `/proc/loadavg` into the 1, 5 and 15 minute averages.
+pub fn parse_loadavg(loadavg: &str) -> Option<(f64, f64, f64)> {
+  let mut values = loadavg.split_whitespace().map(str::parse::<f64>);
+  match (values.next(), values.next(), values.next()) {
+    (Some(Ok(one)), Some(Ok(five)), Some(Ok(fifteen))) => Some((one, five, fifteen)),
+    _ => None,
+  }
+}
+
+/// Parses `/proc/uptime` into whole seconds.
+pub fn parse_uptime(uptime: &str) -> Option<u64> {
+  let seconds = uptime.split_whitespace().next()?.parse::<f64>().ok()?;
+  Some(seconds.max(0.0) as u64)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiskUsage {
+  pub total_bytes: u64,
+  pub used_bytes: u64,
+  pub available_bytes: u64,
+}
+
+/// Parses `df -P -k` output for a single mount. Used space is reported as
+/// total minus what unprivileged users can still write, so reserved blocks
+/// count as used.
+pub fn parse_df(df: &str) -> Option<DiskUsage> {
+  let fields: Vec<&str> = df.lines().nth(1)?.split_whitespace().collect();
+  let kib = |index: usize| fields.get(index)?.parse::<u64>().ok().map(|kib| kib * 1024);
+  let total_bytes = kib(1)?;
+  let available_bytes = kib(3)?;
+  Some(DiskUsage {
+    total_bytes,
+    used_bytes: total_bytes.saturating_sub(available_bytes),
+    available_bytes,
+  })
+}
+
+/// One line of `docker stats --no-stream --format '{{json .}}'`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ContainerStats {
+  #[serde(rename = "Name", default)]
+  pub name: String,
+  #[serde(rename = "CPUPerc", default)]
+  pub cpu_percent: Option<String>,
+  #[serde(rename = "MemPerc", default)]
+  pub memory_percent: Option<String>,
+  #[serde(rename = "MemUsage", default)]
+  pub memory_usage: Option<String>,
+  #[serde(rename = "NetIO", default)]
+  pub network_io: Option<String>,
+  #[serde(rename = "BlockIO", default)]
+  pub block_io: Option<String>,
+  #[serde(rename = "PIDs", default)]
+  pub pids: Option<String>,
+}
+
+/// Parses `docker stats` JSON lines, skipping any that do not decode.
+pub fn parse_docker_stats(output: &str) -> Vec<ContainerStats> {
+  output
+    .lines()
+    .filter_map(|line| serde_json::from_str::<ContainerStats>(line.trim()).ok())
+    .filter(|stats| !stats.name.trim().is_empty())
+    .collect()
+}
+
+/// Format passed to `docker inspect -f`, one line per container.
+pub const INSPECT_FORMAT: &str =
+  "{{.Id}}|{{.Name}}|{{.State.Status}}|{{if .State.Health}}{{.State.Health.Status}}{{else}}none{{end}}";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContainerState {
+  /// Full 64-character container id.
+  pub id: String,
+  /// Container name without Docker's leading `/`.
+  pub name: String,
+  pub status: String,
+  pub health: String,
+}
+
+/// Parses `docker inspect -f` output produced with [`INSPECT_FORMAT`].
+pub fn parse_docker_inspect(output: &str) -> Vec<ContainerState> {
+  output
+    .lines()
+    .filter_map(|line| {
+      let mut parts = line.trim().splitn(4, '|');
+      let id = parts.next()?;
+      let name = parts.next()?.trim_start_matches('/');
+      let status = parts.next()?;
+      let health = parts.next().unwrap_or("none");
+      (!id.is_empty() && !name.is_empty()).then(|| ContainerState {
+        id: id.to_string(),
+        name: name.to_string(),
+        status: status.to_string(),
+        health: health.to_string(),
+      })
+    })
+    .collect()
+}
+
+/// Query run through `psql -At -F '|'`.
+pub const DATABASE_QUERY: &str = "SELECT pg_database_size(current_database()), \
+  (SELECT count(*) FROM pg_stat_activity WHERE datname=current_database()), \
+  (SELECT count(*) FROM rooms), \
+  (SELECT count(*) FROM users), \
+  (SELECT count(*) FROM local_current_membership WHERE membership='join');";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DatabaseCounts {
+  pub size_bytes: u64,
+  pub active_connections: u64,
+  pub room_count: u64,
+  pub user_count: u64,
+  pub joined_memberships: u64,
+}
+
+/// Parses the single `|`-separated row returned for [`DATABASE_QUERY`].
+pub fn parse_database_counts(output: &str) -> Result<DatabaseCounts, &'static str> {
+  let values: Vec<&str> = output.trim().split('|').collect();
+  if values.len() < 5 {
+    return Err("PostgreSQL health response was incomplete.");
+  }
+  let parsed = values
+    .iter()
+    .take(5)
+    .map(|value| value.trim().parse::<u64>())
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|_| "Unable to parse PostgreSQL health response.")?;
+  Ok(DatabaseCounts {
+    size_bytes: parsed[0],
+    active_connections: parsed[1],
+    room_count: parsed[2],
+    user_count: parsed[3],
+    joined_memberships: parsed[4],
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const MEMINFO: &str = "MemTotal:        8000000 kB
+MemFree:          500000 kB
+MemAvailable:    3000000 kB
+Buffers:          100000 kB
+Cached:          1400000 kB
+SwapTotal:             0 kB
+";
+
+  const MEMINFO_LEGACY: &str = "MemTotal:        8000000 kB
+MemFree:          500000 kB
+Buffers:          100000 kB
+Cached:          1400000 kB
+";
+
+  #[test]
+  fn split_sections_keeps_empty_sections_aligned() {
+    let output = format!("a\n{SECTION_MARKER}\n{SECTION_MARKER}\nc1\nc2\n  {SECTION_MARKER}  \n");
+    assert_eq!(split_sections(&output), ["a\n", "", "c1\nc2\n", ""]);
+    assert_eq!(split_sections(""), [""]);
+  }
+
+  #[test]
+  fn parses_cpu_sample_and_percent() {
+    let stat = "cpu  4705 150 1120 16250 520 30 45 0 0 0\ncpu0 2352 75 560 8125 260 15 22 0 0 0\n";
+    let first = parse_cpu_sample(stat).expect("cpu line");
+    assert_eq!(first, CpuSample { total: 22_820, idle: 16_770 });
+
+    let second = CpuSample { total: first.total + 400, idle: first.idle + 100 };
+    assert_eq!(cpu_percent(first, second), Some(75.0));
+    assert_eq!(cpu_percent(first, first), None);
+    assert_eq!(cpu_percent(second, first), None);
+  }
+
+  #[test]
+  fn cpu_sample_rejects_short_or_missing_line() {
+    assert_eq!(parse_cpu_sample("cpu  1 2 3\n"), None);
+    assert_eq!(parse_cpu_sample("cpu0 1 2 3 4 5 6 7\n"), None);
+    assert_eq!(parse_cpu_sample("cpu  1 2 x 4 5 6 7\n"), None);
+  }
+
+  #[test]
+  fn parses_meminfo_with_mem_available() {
+    let memory = parse_meminfo(MEMINFO).expect("meminfo");
+    assert_eq!(memory.total_bytes, 8_000_000 * 1024);
+    assert_eq!(memory.available_bytes, 3_000_000 * 1024);
+    assert_eq!(memory.used_bytes(), 5_000_000 * 1024);
+  }
+
+  #[test]
+  fn parses_meminfo_without_mem_available() {
+    let memory = parse_meminfo(MEMINFO_LEGACY).expect("meminfo");
+    assert_eq!(memory.available_bytes, 2_000_000 * 1024);
+    assert_eq!(memory.used_bytes(), 6_000_000 * 1024);
+    assert_eq!(parse_meminfo("MemFree: 1 kB\n"), None);
+  }
+
+  #[test]
+  fn parses_loadavg_and_uptime() {
+    assert_eq!(parse_loadavg("0.42 0.35 0.30 2/512 12345\n"), Some((0.42, 0.35, 0.30)));
+    assert_eq!(parse_loadavg("0.42\n"), None);
+    assert_eq!(parse_uptime("12345.67 54321.00\n"), Some(12_345));
+    assert_eq!(parse_uptime(""), None);
+  }
+
+  #[test]
+  fn parses_df_counting_reserved_blocks_as_used() {
+    let df = "Filesystem     1024-blocks     Used Available Capacity Mounted on
+/dev/vda1         81253768 40000000  37000000      52% /
+";
+    let disk = parse_df(df).expect("df");
+    assert_eq!(disk.total_bytes, 81_253_768 * 1024);
+    assert_eq!(disk.available_bytes, 37_000_000 * 1024);
+    assert_eq!(disk.used_bytes, (81_253_768 - 37_000_000) * 1024);
+    assert_eq!(parse_df("Filesystem 1024-blocks Used Available Capacity Mounted on\n"), None);
+  }
+
+  #[test]
+  fn parses_docker_stats_lines() {
+    let output = concat!(
+      r#"{"BlockIO":"1.2MB / 0B","CPUPerc":"0.85%","Container":"abc","ID":"abc","MemPerc":"6.10%","MemUsage":"120MiB / 1.9GiB","Name":"fray-synapse","NetIO":"5kB / 3kB","PIDs":"12"}"#,
+      "\n",
+      "Error response from daemon\n",
+      r#"{"Name":"","CPUPerc":"0%"}"#,
+      "\n",
+      r#"{"Name":"fray-postgres"}"#,
+      "\n",
+    );
+    let stats = parse_docker_stats(output);
+    assert_eq!(stats.len(), 2);
+    assert_eq!(stats[0].name, "fray-synapse");
+    assert_eq!(stats[0].cpu_percent.as_deref(), Some("0.85%"));
+    assert_eq!(stats[0].memory_usage.as_deref(), Some("120MiB / 1.9GiB"));
+    assert_eq!(stats[0].pids.as_deref(), Some("12"));
+    assert_eq!(stats[1].name, "fray-postgres");
+    assert!(stats[1].cpu_percent.is_none());
+  }
+
+  #[test]
+  fn parses_docker_inspect_lines() {
+    let output = "aa11|/fray-synapse|running|healthy\nbb22|/fray-postgres|exited\n\ncc33||running|none\n";
+    assert_eq!(
+      parse_docker_inspect(output),
+      [
+        ContainerState {
+          id: "aa11".into(),
+          name: "fray-synapse".into(),
+          status: "running".into(),
+          health: "healthy".into(),
+        },
+        ContainerState {
+          id: "bb22".into(),
+          name: "fray-postgres".into(),
+          status: "exited".into(),
+          health: "none".into(),
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn parses_database_counts_row() {
+    assert_eq!(
+      parse_database_counts("52428800|4|120|35|410\n"),
+      Ok(DatabaseCounts {
+        size_bytes: 52_428_800,
+        active_connections: 4,
+        room_count: 120,
+        user_count: 35,
+        joined_memberships: 410,
+      })
+    );
+    assert_eq!(
+      parse_database_counts("52428800|4|120\n"),
+      Err("PostgreSQL health response was incomplete.")
+    );
+    assert_eq!(
+      parse_database_counts("52428800|4|x|35|410\n"),
+      Err("Unable to parse PostgreSQL health response.")
+    );
+  }
+}
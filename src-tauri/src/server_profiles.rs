@@ -0,0 +1,471 @@
+//! Saved server profiles.
+//!
+//! Connection settings live in `server_profiles.json` in the app data dir.
+//! SSH passwords, key passphrases and the Synapse admin access token are kept
+//! in the OS keychain (Secret Service on Linux, Keychain on macOS, Credential
+//! Manager on Windows) and are only read back on the Rust side, so commands
+//! that reach a server or its admin API take a profile id rather than raw
+//! credentials.
+
+use crate::app_data::{read_json_file, write_json_file};
+use crate::error::{CommandError, CommandResult, ErrorKind};
+use crate::health_history::HealthHistoryStore;
+use crate::health_monitor::HealthMonitorStore;
+use crate::keychain;
+use crate::known_hosts::KnownHostsStore;
+use crate::now_millis;
+use crate::server_health::HealthTargets;
+use crate::ssh::{SshSession, SshTarget, DEFAULT_SSH_PORT};
+use crate::synapse_admin::de::double_option;
+use crate::synapse_admin::{normalize_base_url, SynapseAdminClient};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use tauri::{AppHandle, Manager, State};
+
+pub const SERVER_PROFILES_FILE: &str = "server_profiles.json";
+const KEYCHAIN_SERVICE: &str = "fray.server-profiles";
+
+#[derive(Debug, Clone, Copy)]
+enum Secret {
+  SshPassword,
+  KeyPassphrase,
+  AccessToken,
+}
+
+impl Secret {
+  fn slot(self) -> &'static str {
+    match self {
+      Secret::SshPassword => "ssh-password",
+      Secret::KeyPassphrase => "ssh-key-passphrase",
+      Secret::AccessToken => "admin-access-token",
+    }
+  }
+}
+
+fn keychain_account(profile_id: &str, secret: Secret) -> String {
+  format!("{profile_id}/{}", secret.slot())
+}
+
+fn read_secret(profile_id: &str, secret: Secret) -> CommandResult<Option<String>> {
+  keychain::read_secret(KEYCHAIN_SERVICE, &keychain_account(profile_id, secret))
+}
+
+/// Stores `value`, or removes the entry when it is `None`.
+fn write_secret(profile_id: &str, secret: Secret, value: Option<&str>) -> CommandResult<()> {
+  keychain::write_secret(KEYCHAIN_SERVICE, &keychain_account(profile_id, secret), value)
+}
+
+fn valid_profile_id(id: &str) -> bool {
+  // `/` separates the id from the secret slot in keychain entry names.
+  !id.is_empty()
+    && id.len() <= 255
+    && !id.chars().any(|character| character == '/' || character.is_control())
+}
+
+/// Profile metadata as stored on disk and returned to the frontend. Secrets
+/// are reported only as presence flags.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerProfile {
+  pub id: String,
+  pub name: String,
+  pub host: String,
+  pub port: u16,
+  pub username: String,
+  #[serde(default)]
+  pub private_key_path: Option<String>,
+  pub synapse_container: String,
+  pub postgres_container: String,
+  pub postgres_user: String,
+  pub postgres_db: String,
+  /// Remote directory holding the `docker compose` project, if any.
+  #[serde(default)]
+  pub compose_project_dir: Option<String>,
+  /// Homeserver the admin commands talk to.
+  #[serde(default)]
+  pub homeserver_url: Option<String>,
+  #[serde(default)]
+  pub has_password: bool,
+  #[serde(default)]
+  pub has_key_passphrase: bool,
+  #[serde(default)]
+  pub has_access_token: bool,
+  pub created_at: u64,
+  pub updated_at: u64,
+}
+
+impl ServerProfile {
+  pub fn health_targets(&self) -> HealthTargets {
+    HealthTargets {
+      synapse_container: self.synapse_container.clone(),
+      postgres_container: self.postgres_container.clone(),
+      postgres_user: self.postgres_user.clone(),
+      postgres_db: self.postgres_db.clone(),
+    }
+  }
+
+  /// Resolves the SSH target, reading stored secrets from the keychain.
+  /// Blocking: the keychain may wait on an unlock prompt.
+  fn ssh_target(&self) -> CommandResult<SshTarget> {
+    let password = if self.has_password {
+      read_secret(&self.id, Secret::SshPassword)?
+    } else {
+      None
+    };
+    let key_passphrase = if self.has_key_passphrase {
+      read_secret(&self.id, Secret::KeyPassphrase)?
+    } else {
+      None
+    };
+    SshTarget::new(
+      &self.host,
+      Some(self.port),
+      &self.username,
+      password,
+      self.private_key_path.clone(),
+      key_passphrase,
+    )
+  }
+}
+
+/// Create/update payload. `id` may be omitted to create a profile with a
+/// generated id. For `port`, `private_key_path`, `compose_project_dir`,
+/// `homeserver_url` and the secrets, an absent field keeps the stored value
+/// while `null` or an empty string clears it (`port` falls back to 22).
+#[derive(Debug, Deserialize)]
+pub struct ServerProfileInput {
+  #[serde(default)]
+  pub id: Option<String>,
+  #[serde(default)]
+  pub name: Option<String>,
+  pub host: String,
+  #[serde(default, deserialize_with = "double_option")]
+  pub port: Option<Option<u16>>,
+  pub username: String,
+  #[serde(default, deserialize_with = "double_option")]
+  pub private_key_path: Option<Option<String>>,
+  #[serde(default)]
+  pub synapse_container: Option<String>,
+  #[serde(default)]
+  pub postgres_container: Option<String>,
+  #[serde(default)]
+  pub postgres_user: Option<String>,
+  #[serde(default)]
+  pub postgres_db: Option<String>,
+  #[serde(default, deserialize_with = "double_option")]
+  pub compose_project_dir: Option<Option<String>>,
+  #[serde(default, deserialize_with = "double_option")]
+  pub homeserver_url: Option<Option<String>>,
+  #[serde(default, deserialize_with = "double_option")]
+  pub password: Option<Option<String>>,
+  #[serde(default, deserialize_with = "double_option")]
+  pub key_passphrase: Option<Option<String>>,
+  #[serde(default, deserialize_with = "double_option")]
+  pub access_token: Option<Option<String>>,
+}
+
+/// Applies an optional field update: absent keeps `current`, while `null` or
+/// an empty string clears it.
+fn field_update(update: Option<Option<String>>, current: Option<&String>) -> Option<String> {
+  match update {
+    Some(value) => value.map(|value| value.trim().to_string()).filter(|value| !value.is_empty()),
+    None => current.cloned(),
+  }
+}
+
+/// Normalizes a secret update: `Some(None)` clears, `Some(Some(_))` sets.
+fn secret_update(update: Option<Option<String>>) -> Option<Option<String>> {
+  update.map(|value| value.filter(|value| !value.is_empty()))
+}
+
+pub struct ServerProfileStore {
+  path: PathBuf,
+  profiles: Mutex<Vec<ServerProfile>>,
+}
+
+impl ServerProfileStore {
+  pub fn load(path: PathBuf) -> Self {
+    let profiles = read_json_file(&path);
+    Self {
+      path,
+      profiles: Mutex::new(profiles),
+    }
+  }
+
+  fn lock(&self) -> MutexGuard<'_, Vec<ServerProfile>> {
+    self.profiles.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+
+  pub fn get(&self, id: &str) -> CommandResult<ServerProfile> {
+    self
+      .lock()
+      .iter()
+      .find(|profile| profile.id == id)
+      .cloned()
+      .ok_or_else(|| CommandError::new(ErrorKind::NotFound, format!("No saved server profile {id}.")))
+  }
+
+  pub fn list(&self) -> Vec<ServerProfile> {
+    self.lock().clone()
+  }
+
+  /// Validates and stores a profile, writing any secret changes to the
+  /// keychain before the metadata so a failed keychain write saves nothing.
+  fn save(&self, input: ServerProfileInput) -> CommandResult<ServerProfile> {
+    let host = input.host.trim().to_string();
+    let username = input.username.trim().to_string();
+    if host.is_empty() || username.is_empty() {
+      return Err(CommandError::invalid_input("Host and SSH username are required."));
+    }
+    let id = match input.id.map(|id| id.trim().to_string()) {
+      Some(id) if !valid_profile_id(&id) => {
+        return Err(CommandError::invalid_input(
+          "Profile ids must be non-empty and may not contain '/' or control characters.",
+        ))
+      }
+      Some(id) => id,
+      None => uuid::Uuid::new_v4().to_string(),
+    };
+    let existing = self.lock().iter().find(|profile| profile.id == id).cloned();
+
+    let password = secret_update(input.password);
+    let key_passphrase = secret_update(input.key_passphrase);
+    if let Some(value) = &password {
+      write_secret(&id, Secret::SshPassword, value.as_deref())?;
+    }
+    if let Some(value) = &key_passphrase {
+      write_secret(&id, Secret::KeyPassphrase, value.as_deref())?;
+    }
+    let access_token = secret_update(input.access_token.map(|value| value.map(|token| token.trim().to_string())));
+    if let Some(value) = &access_token {
+      write_secret(&id, Secret::AccessToken, value.as_deref())?;
+    }
+
+    let targets = HealthTargets::new(
+      input.synapse_container,
+      input.postgres_container,
+      input.postgres_user,
+      input.postgres_db,
+    );
+    let now = now_millis();
+    let profile = ServerProfile {
+      name: input
+        .name
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| host.clone()),
+      port: match input.port {
+        Some(port) => port.unwrap_or(DEFAULT_SSH_PORT),
+        None => existing.as_ref().map_or(DEFAULT_SSH_PORT, |profile| profile.port),
+      },
+      private_key_path: field_update(
+        input.private_key_path,
+        existing.as_ref().and_then(|profile| profile.private_key_path.as_ref()),
+      ),
+      synapse_container: targets.synapse_container,
+      postgres_container: targets.postgres_container,
+      postgres_user: targets.postgres_user,
+      postgres_db: targets.postgres_db,
+      compose_project_dir: field_update(
+        input.compose_project_dir,
+        existing.as_ref().and_then(|profile| profile.compose_project_dir.as_ref()),
+      ),
+      homeserver_url: field_update(
+        input.homeserver_url.map(|url| url.map(|url| normalize_base_url(&url))),
+        existing.as_ref().and_then(|profile| profile.homeserver_url.as_ref()),
+      ),
+      has_password: match &password {
+        Some(value) => value.is_some(),
+        None => existing.as_ref().is_some_and(|profile| profile.has_password),
+      },
+      has_key_passphrase: match &key_passphrase {
+        Some(value) => value.is_some(),
+        None => existing.as_ref().is_some_and(|profile| profile.has_key_passphrase),
+      },
+      has_access_token: match &access_token {
+        Some(value) => value.is_some(),
+        None => existing.as_ref().is_some_and(|profile| profile.has_access_token),
+      },
+      created_at: existing.as_ref().map_or(now, |profile| profile.created_at),
+      updated_at: now,
+      id,
+      host,
+      username,
+    };
+
+    let mut profiles = self.lock();
+    match profiles.iter_mut().find(|stored| stored.id == profile.id) {
+      Some(stored) => *stored = profile.clone(),
+      None => profiles.push(profile.clone()),
+    }
+    write_json_file(&self.path, &*profiles);
+    Ok(profile)
+  }
+
+  /// Removes the profile, then its keychain entries. A keychain failure is
+  /// logged rather than returned so a locked keychain never pins a profile.
+  fn delete(&self, id: &str) -> bool {
+    let removed = {
+      let mut profiles = self.lock();
+      let before = profiles.len();
+      profiles.retain(|profile| profile.id != id);
+      let removed = profiles.len() != before;
+      if removed {
+        write_json_file(&self.path, &*profiles);
+      }
+      removed
+    };
+    for secret in [Secret::SshPassword, Secret::KeyPassphrase, Secret::AccessToken] {
+      if let Err(error) = write_secret(id, secret, None) {
+        log::warn!("Failed to remove {} for server profile {id}: {error}", secret.slot());
+      }
+    }
+    removed
+  }
+}
+
+/// Opens an authenticated SSH session for a saved profile. Blocking; call from
+/// `spawn_blocking`.
+pub fn open_session(app: &AppHandle, profile_id: &str) -> CommandResult<(ServerProfile, SshSession)> {
+  let profile = app.state::<ServerProfileStore>().get(profile_id)?;
+  let target = profile.ssh_target()?;
+  let session = SshSession::connect(&target, &app.state::<KnownHostsStore>())?;
+  Ok((profile, session))
+}
+
+/// Resolves a profile's homeserver URL and admin access token, reading the
+/// token from the keychain off the async runtime. A missing profile, URL or
+/// token is `NotFound` without a `status`, so callers can tell "not set up"
+/// apart from a 404 returned by Synapse.
+pub async fn admin_credentials(app: &AppHandle, profile_id: &str) -> CommandResult<(String, String)> {
+  let profile = app.state::<ServerProfileStore>().get(profile_id)?;
+  let homeserver_url = profile.homeserver_url.clone().ok_or_else(|| {
+    CommandError::new(ErrorKind::NotFound, "This server profile has no homeserver URL configured.")
+  })?;
+  let missing_token =
+    || CommandError::new(ErrorKind::NotFound, "This server profile has no admin access token saved.");
+  if !profile.has_access_token {
+    return Err(missing_token());
+  }
+  let access_token = tauri::async_runtime::spawn_blocking(move || read_secret(&profile.id, Secret::AccessToken))
+    .await
+    .map_err(|error| CommandError::internal(format!("Reading admin access token failed: {error}")))??
+    .ok_or_else(missing_token)?;
+  Ok((homeserver_url, access_token))
+}
+
+/// Admin API client for a saved profile's homeserver.
+pub async fn admin_client(app: &AppHandle, profile_id: &str) -> CommandResult<SynapseAdminClient> {
+  let (homeserver_url, access_token) = admin_credentials(app, profile_id).await?;
+  Ok(SynapseAdminClient::new(&homeserver_url, &access_token))
+}
+
+#[tauri::command]
+pub fn list_server_profiles(store: State<'_, ServerProfileStore>) -> Vec<ServerProfile> {
+  store.list()
+}
+
+#[tauri::command]
+pub async fn save_server_profile(app: AppHandle, profile: ServerProfileInput) -> CommandResult<ServerProfile> {
+  tauri::async_runtime::spawn_blocking(move || app.state::<ServerProfileStore>().save(profile))
+    .await
+    .map_err(|error| CommandError::internal(format!("Saving server profile failed: {error}")))?
+}
+
+/// Returns whether a profile was removed. Its health history and monitor go
+/// with it.
+#[tauri::command]
+pub async fn delete_server_profile(app: AppHandle, id: String) -> CommandResult<bool> {
+  tauri::async_runtime::spawn_blocking(move || {
+    let removed = app.state::<ServerProfileStore>().delete(&id);
+    app.state::<HealthMonitorStore>().remove(&id);
+    if let Err(error) = app.state::<HealthHistoryStore>().clear(&id) {
+      log::warn!("Failed to clear health history for server profile {id}: {error}");
+    }
+    removed
+  })
+    .await
+    .map_err(|error| CommandError::internal(format!("Deleting server profile failed: {error}")))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  fn scratch_store(name: &str) -> ServerProfileStore {
+    let dir = std::env::temp_dir().join(format!("fray-{name}-{}-{}", std::process::id(), now_millis()));
+    ServerProfileStore::load(dir.join(SERVER_PROFILES_FILE))
+  }
+
+  fn input(value: serde_json::Value) -> ServerProfileInput {
+    serde_json::from_value(value).unwrap()
+  }
+
+  #[test]
+  fn resave_without_optional_fields_keeps_them() {
+    let store = scratch_store("profile-resave");
+    store
+      .save(input(json!({
+        "id": "hq",
+        "host": "matrix.example.com",
+        "username": "root",
+        "port": 2222,
+        "private_key_path": "~/.ssh/id_ed25519",
+        "compose_project_dir": "/srv/matrix",
+        "homeserver_url": "https://matrix.example.com/",
+      })))
+      .unwrap();
+
+    let profile = store
+      .save(input(json!({
+        "id": "hq",
+        "host": "matrix.example.com",
+        "username": "admin",
+        "synapse_container": "synapse",
+      })))
+      .unwrap();
+    assert_eq!(profile.username, "admin");
+    assert_eq!(profile.synapse_container, "synapse");
+    assert_eq!(profile.port, 2222);
+    assert_eq!(profile.private_key_path.as_deref(), Some("~/.ssh/id_ed25519"));
+    assert_eq!(profile.compose_project_dir.as_deref(), Some("/srv/matrix"));
+    assert_eq!(profile.homeserver_url.as_deref(), Some("https://matrix.example.com"));
+
+    let _ = std::fs::remove_dir_all(store.path.parent().unwrap());
+  }
+
+  #[test]
+  fn explicit_null_or_empty_clears_optional_fields() {
+    let store = scratch_store("profile-clear");
+    store
+      .save(input(json!({
+        "id": "hq",
+        "host": "matrix.example.com",
+        "username": "root",
+        "port": 2222,
+        "private_key_path": "~/.ssh/id_ed25519",
+        "compose_project_dir": "/srv/matrix",
+        "homeserver_url": "https://matrix.example.com",
+      })))
+      .unwrap();
+
+    let profile = store
+      .save(input(json!({
+        "id": "hq",
+        "host": "matrix.example.com",
+        "username": "root",
+        "port": null,
+        "private_key_path": null,
+        "compose_project_dir": "  ",
+        "homeserver_url": "",
+      })))
+      .unwrap();
+    assert_eq!(profile.port, DEFAULT_SSH_PORT);
+    assert_eq!(profile.private_key_path, None);
+    assert_eq!(profile.compose_project_dir, None);
+    assert_eq!(profile.homeserver_url, None);
+
+    let _ = std::fs::remove_dir_all(store.path.parent().unwrap());
+  }
+}
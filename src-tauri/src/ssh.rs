@@ -0,0 +1,422 @@
+//! In-process SSH client used for server diagnostics.
+//!
+//! Replaces shelling out to `ssh`/`sshpass`, so diagnostics work on machines
+//! without those binaries and passwords never pass through the environment.
+//! Callers depend on [`RemoteShell`] so a local stand-in can replace the real
+//! session. Host keys are checked against the [`KnownHostsStore`] before any
+//! credentials are sent.
+
+use crate::error::{CommandError, CommandResult, ErrorKind};
+use crate::known_hosts::{HostKey, KnownHostsStore};
+use base64::engine::general_purpose::STANDARD_NO_PAD;
+use base64::Engine;
+use ssh2::{Channel, ErrorCode, HashType, HostKeyType, MethodType, Session};
+use std::io::{ErrorKind as IoErrorKind, Read};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_SSH_PORT: u16 = 22;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Upper bound for any single blocking libssh2 call, including remote commands.
+const SESSION_TIMEOUT: Duration = Duration::from_secs(60);
+const READ_BUFFER_BYTES: usize = 16 * 1024;
+/// How long to wait before polling an idle command's output again.
+const DRAIN_IDLE_POLL: Duration = Duration::from_millis(20);
+/// `LIBSSH2_ERROR_TIMEOUT`.
+const LIBSSH2_ERROR_TIMEOUT: i32 = -9;
+/// Identity files tried, in order, when the agent has no accepted key.
+const DEFAULT_IDENTITY_FILES: [&str; 3] = ["id_ed25519", "id_ecdsa", "id_rsa"];
+
+#[derive(Debug, Clone)]
+pub enum SshAuth {
+  Password(String),
+  KeyFile {
+    path: PathBuf,
+    passphrase: Option<String>,
+  },
+  /// The running ssh-agent, then the default identity files in `~/.ssh`.
+  Agent,
+}
+
+#[derive(Debug, Clone)]
+pub struct SshTarget {
+  pub host: String,
+  pub port: u16,
+  pub username: String,
+  pub auth: SshAuth,
+}
+
+impl SshTarget {
+  /// Picks the auth method from the optional credentials the frontend sends:
+  /// a password wins, then an explicit key file, then the agent.
+  pub fn new(
+    host: &str,
+    port: Option<u16>,
+    username: &str,
+    password: Option<String>,
+    private_key_path: Option<String>,
+    key_passphrase: Option<String>,
+  ) -> CommandResult<Self> {
+    let host = host.trim();
+    let username = username.trim();
+    if host.is_empty() || username.is_empty() {
+      return Err(CommandError::invalid_input("Host and SSH username are required."));
+    }
+    let password = password.filter(|password| !password.trim().is_empty());
+    let private_key_path = private_key_path
+      .map(|path| path.trim().to_string())
+      .filter(|path| !path.is_empty());
+    let auth = match (password, private_key_path) {
+      (Some(password), _) => SshAuth::Password(password),
+      (None, Some(path)) => SshAuth::KeyFile {
+        path: expand_home(&path),
+        passphrase: key_passphrase.filter(|passphrase| !passphrase.is_empty()),
+      },
+      (None, None) => SshAuth::Agent,
+    };
+    Ok(Self {
+      host: host.to_string(),
+      port: port.unwrap_or(DEFAULT_SSH_PORT),
+      username: username.to_string(),
+      auth,
+    })
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct RemoteOutput {
+  pub exit_status: i32,
+  pub stdout: String,
+  pub stderr: String,
+}
+
+pub trait RemoteShell {
+  fn run(&self, command: &str) -> CommandResult<RemoteOutput>;
+}
+
+/// Quotes a value for interpolation into a POSIX shell command.
+pub fn shell_escape(value: &str) -> String {
+  format!("'{}'", value.replace('\'', "'\"'\"'"))
+}
+
+/// Quotes a remote path like [`shell_escape`], but keeps a leading `~` or
+/// `~/` pointing at the remote user's home, which quoting would otherwise
+/// turn into a literal `~` directory.
+pub fn shell_escape_path(path: &str) -> String {
+  match path.strip_prefix('~') {
+    Some("") => "\"$HOME\"".to_string(),
+    Some(rest) if rest.starts_with('/') => match rest.trim_start_matches('/') {
+      "" => "\"$HOME\"".to_string(),
+      rest => format!("\"$HOME\"/{}", shell_escape(rest)),
+    },
+    _ => shell_escape(path),
+  }
+}
+
+fn home_dir() -> Option<PathBuf> {
+  std::env::var_os("HOME")
+    .or_else(|| std::env::var_os("USERPROFILE"))
+    .map(PathBuf::from)
+}
+
+fn expand_home(path: &str) -> PathBuf {
+  match (path.strip_prefix("~/"), home_dir()) {
+    (Some(rest), Some(home)) => home.join(rest),
+    _ => PathBuf::from(path),
+  }
+}
+
+fn key_type_name(key_type: HostKeyType) -> &'static str {
+  match key_type {
+    HostKeyType::Rsa => "ssh-rsa",
+    HostKeyType::Dss => "ssh-dss",
+    HostKeyType::Ecdsa256 => "ecdsa-sha2-nistp256",
+    HostKeyType::Ecdsa384 => "ecdsa-sha2-nistp384",
+    HostKeyType::Ecdsa521 => "ecdsa-sha2-nistp521",
+    HostKeyType::Ed25519 => "ssh-ed25519",
+    HostKeyType::Unknown => "unknown",
+  }
+}
+
+/// Host key algorithms to negotiate for a trusted key type. An RSA key can be
+/// signed with SHA-2 or SHA-1; OpenSSH 8.8+ refuses the SHA-1 `ssh-rsa`
+/// signature, so the SHA-2 variants go first.
+fn host_key_methods(key_type: &str) -> &str {
+  match key_type {
+    "ssh-rsa" => "rsa-sha2-512,rsa-sha2-256,ssh-rsa",
+    other => other,
+  }
+}
+
+fn ssh_error(kind: ErrorKind, context: &str, error: ssh2::Error) -> CommandError {
+  let kind = if error.code() == ErrorCode::Session(LIBSSH2_ERROR_TIMEOUT) {
+    ErrorKind::Timeout
+  } else {
+    kind
+  };
+  CommandError::new(kind, format!("{context}: {}", error.message()))
+}
+
+/// Appends whatever `stream` has ready to `output`. Returns whether anything
+/// was read.
+fn read_ready(stream: &mut impl Read, buffer: &mut [u8], output: &mut Vec<u8>) -> CommandResult<bool> {
+  let mut progressed = false;
+  loop {
+    match stream.read(buffer) {
+      Ok(0) => return Ok(progressed),
+      Ok(read) => {
+        output.extend_from_slice(&buffer[..read]);
+        progressed = true;
+      }
+      Err(error) if error.kind() == IoErrorKind::WouldBlock => return Ok(progressed),
+      Err(error) => {
+        return Err(CommandError::new(
+          ErrorKind::Ssh,
+          format!("Failed to read remote output: {error}"),
+        ))
+      }
+    }
+  }
+}
+
+pub struct SshSession {
+  session: Session,
+}
+
+impl SshSession {
+  pub fn connect(target: &SshTarget, known_hosts: &KnownHostsStore) -> CommandResult<Self> {
+    let address = (target.host.as_str(), target.port)
+      .to_socket_addrs()
+      .map_err(|error| {
+        CommandError::new(
+          ErrorKind::Network,
+          format!("Could not resolve {}: {error}", target.host),
+        )
+      })?
+      .next()
+      .ok_or_else(|| CommandError::new(ErrorKind::Network, format!("Could not resolve {}.", target.host)))?;
+    let stream = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT).map_err(|error| {
+      let kind = if error.kind() == std::io::ErrorKind::TimedOut {
+        ErrorKind::Timeout
+      } else {
+        ErrorKind::Network
+      };
+      CommandError::new(kind, format!("Could not connect to {address}: {error}"))
+    })?;
+
+    let mut session = Session::new()
+      .map_err(|error| ssh_error(ErrorKind::SshLaunch, "Failed to start SSH session", error))?;
+    session.set_tcp_stream(stream);
+    session.set_timeout(SESSION_TIMEOUT.as_millis() as u32);
+    if let Some(key_type) = known_hosts.expected_key_type(&target.host, target.port) {
+      // Best effort: older servers may not offer the trusted algorithm first.
+      let _ = session.method_pref(MethodType::HostKey, host_key_methods(&key_type));
+    }
+    session
+      .handshake()
+      .map_err(|error| ssh_error(ErrorKind::SshLaunch, "SSH handshake failed", error))?;
+
+    let connected = Self { session };
+    known_hosts.verify(&connected.host_key(target)?)?;
+    connected.authenticate(target)?;
+    Ok(connected)
+  }
+
+  fn host_key(&self, target: &SshTarget) -> CommandResult<HostKey> {
+    let (_, key_type) = self
+      .session
+      .host_key()
+      .ok_or_else(|| CommandError::new(ErrorKind::SshLaunch, "SSH server did not present a host key."))?;
+    let hash = self
+      .session
+      .host_key_hash(HashType::Sha256)
+      .ok_or_else(|| CommandError::new(ErrorKind::SshLaunch, "Unable to fingerprint the SSH host key."))?;
+    Ok(HostKey {
+      host: target.host.clone(),
+      port: target.port,
+      key_type: key_type_name(key_type).to_string(),
+      fingerprint: format!("SHA256:{}", STANDARD_NO_PAD.encode(hash)),
+    })
+  }
+
+  fn authenticate(&self, target: &SshTarget) -> CommandResult<()> {
+    let username = target.username.as_str();
+    match &target.auth {
+      SshAuth::Password(password) => self
+        .session
+        .userauth_password(username, password)
+        .map_err(|error| ssh_error(ErrorKind::SshAuth, "SSH password authentication failed", error)),
+      SshAuth::KeyFile { path, passphrase } => self
+        .session
+        .userauth_pubkey_file(username, None, path, passphrase.as_deref())
+        .map_err(|error| ssh_error(ErrorKind::SshAuth, "SSH key authentication failed", error)),
+      SshAuth::Agent => self.authenticate_default(username),
+    }
+  }
+
+  fn authenticate_default(&self, username: &str) -> CommandResult<()> {
+    if self.session.userauth_agent(username).is_ok() && self.session.authenticated() {
+      return Ok(());
+    }
+    let ssh_dir = home_dir().map(|home| home.join(".ssh"));
+    for name in DEFAULT_IDENTITY_FILES {
+      let Some(path) = ssh_dir.as_deref().map(|dir: &Path| dir.join(name)) else {
+        break;
+      };
+      if path.is_file()
+        && self
+          .session
+          .userauth_pubkey_file(username, None, &path, None)
+          .is_ok()
+        && self.session.authenticated()
+      {
+        return Ok(());
+      }
+    }
+    Err(CommandError::new(
+      ErrorKind::SshAuth,
+      "SSH authentication failed: no ssh-agent identity or default key was accepted. Provide a password or key file.",
+    ))
+  }
+
+  /// Overrides the per-call timeout for commands that legitimately run long.
+  pub fn set_command_timeout(&self, timeout: Duration) {
+    self.session.set_timeout(timeout.as_millis() as u32);
+  }
+
+  /// Starts a long-running command and switches the session to non-blocking
+  /// mode so its output can be read incrementally.
+  ///
+  /// The command runs on a pseudo-terminal: without one, sshd leaves a
+  /// command like `docker logs --follow` running after the channel closes
+  /// until it next fails to write. With one, closing the channel hangs up the
+  /// terminal and the kernel sends the command SIGHUP. Lines arrive with
+  /// `\r\n` endings.
+  pub fn stream(self, command: &str) -> CommandResult<RemoteStream> {
+    let mut channel = self
+      .session
+      .channel_session()
+      .map_err(|error| ssh_error(ErrorKind::SshLaunch, "Failed to open SSH channel", error))?;
+    channel
+      .request_pty("dumb", None, None)
+      .map_err(|error| ssh_error(ErrorKind::SshLaunch, "Failed to allocate a remote terminal", error))?;
+    channel
+      .exec(command)
+      .map_err(|error| ssh_error(ErrorKind::Ssh, "Failed to run remote command", error))?;
+    self.session.set_blocking(false);
+    Ok(RemoteStream {
+      session: self.session,
+      channel,
+    })
+  }
+
+  /// Reads stdout and stderr until the remote side closes them. The session
+  /// must be non-blocking. Gives up when neither stream moves for the session
+  /// timeout, matching what a blocking read would do.
+  fn drain(&self, channel: &mut Channel) -> CommandResult<(Vec<u8>, Vec<u8>)> {
+    let idle_limit = Duration::from_millis(u64::from(self.session.timeout()));
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    let mut buffer = vec![0; READ_BUFFER_BYTES];
+    let mut last_progress = Instant::now();
+    loop {
+      let read_stdout = read_ready(channel, &mut buffer, &mut stdout)?;
+      let read_stderr = read_ready(&mut channel.stderr(), &mut buffer, &mut stderr)?;
+      if read_stdout || read_stderr {
+        last_progress = Instant::now();
+        continue;
+      }
+      if channel.eof() {
+        return Ok((stdout, stderr));
+      }
+      if !idle_limit.is_zero() && last_progress.elapsed() >= idle_limit {
+        return Err(CommandError::new(
+          ErrorKind::Timeout,
+          "Remote command produced no output before the SSH timeout.",
+        ));
+      }
+      std::thread::sleep(DRAIN_IDLE_POLL);
+    }
+  }
+}
+
+pub enum StreamRead {
+  Data(usize),
+  /// Nothing available yet; poll again later.
+  Idle,
+  Closed,
+}
+
+/// Output of a command started with [`SshSession::stream`]. stdout and stderr
+/// share the remote terminal, so both arrive through `read`.
+pub struct RemoteStream {
+  session: Session,
+  channel: Channel,
+}
+
+impl RemoteStream {
+  pub fn read(&mut self, buffer: &mut [u8]) -> CommandResult<StreamRead> {
+    match self.channel.read(buffer) {
+      Ok(0) if self.channel.eof() => Ok(StreamRead::Closed),
+      Ok(0) => Ok(StreamRead::Idle),
+      Ok(read) => Ok(StreamRead::Data(read)),
+      Err(error) if error.kind() == IoErrorKind::WouldBlock => Ok(StreamRead::Idle),
+      Err(error) => Err(CommandError::new(
+        ErrorKind::Ssh,
+        format!("Failed to read remote output: {error}"),
+      )),
+    }
+  }
+
+  /// Closes the channel, which hangs up the remote terminal and so ends the
+  /// command.
+  pub fn close(mut self) {
+    self.session.set_blocking(true);
+    if let Err(error) = self.channel.close() {
+      log::warn!("Failed to close SSH channel: {}", error.message());
+    }
+  }
+}
+
+impl RemoteShell for SshSession {
+  fn run(&self, command: &str) -> CommandResult<RemoteOutput> {
+    let mut channel = self
+      .session
+      .channel_session()
+      .map_err(|error| ssh_error(ErrorKind::SshLaunch, "Failed to open SSH channel", error))?;
+    channel
+      .exec(command)
+      .map_err(|error| ssh_error(ErrorKind::Ssh, "Failed to run remote command", error))?;
+
+    // Both streams share the channel window, so they are drained together: a
+    // command that fills stderr while stdout is being read would stall.
+    self.session.set_blocking(false);
+    let output = self.drain(&mut channel);
+    self.session.set_blocking(true);
+    let (stdout, stderr) = output?;
+    channel
+      .wait_close()
+      .map_err(|error| ssh_error(ErrorKind::Ssh, "Remote command did not finish", error))?;
+    let exit_status = channel
+      .exit_status()
+      .map_err(|error| ssh_error(ErrorKind::Ssh, "Remote command status unavailable", error))?;
+
+    Ok(RemoteOutput {
+      exit_status,
+      stdout: String::from_utf8_lossy(&stdout).into_owned(),
+      stderr: String::from_utf8_lossy(&stderr).into_owned(),
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn rsa_host_keys_negotiate_sha2_signatures() {
+    assert_eq!(host_key_methods("ssh-rsa"), "rsa-sha2-512,rsa-sha2-256,ssh-rsa");
+    assert_eq!(host_key_methods("ssh-ed25519"), "ssh-ed25519");
+  }
+}
@@ -0,0 +1,139 @@
+use super::client::SynapseAdminClient;
+use crate::error::CommandResult;
+use crate::server_profiles::admin_client;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use tauri::AppHandle;
+
+#[derive(Debug, Deserialize)]
+struct RawUpdate {
+  name: String,
+  #[serde(default)]
+  total_item_count: u64,
+  #[serde(default)]
+  total_duration_ms: f64,
+  #[serde(default)]
+  average_items_per_ms: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawStatus {
+  #[serde(default)]
+  enabled: bool,
+  /// Keyed by database name; usually just `master`.
+  #[serde(default)]
+  current_updates: BTreeMap<String, RawUpdate>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EnabledResponse {
+  enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackgroundUpdate {
+  pub database: String,
+  pub name: String,
+  pub total_item_count: u64,
+  pub total_duration_ms: f64,
+  pub average_items_per_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackgroundUpdatesStatus {
+  pub enabled: bool,
+  /// Updates currently running, one per database at most.
+  pub current_updates: Vec<BackgroundUpdate>,
+}
+
+/// Jobs that `background_updates/start_job` accepts.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackgroundJob {
+  /// Recalculates room statistics used by the admin rooms list.
+  PopulateStatsProcessRooms,
+  /// Rebuilds the user directory.
+  RegenerateDirectory,
+}
+
+impl SynapseAdminClient {
+  pub async fn background_updates_status(&self) -> CommandResult<BackgroundUpdatesStatus> {
+    let raw: RawStatus = self
+      .get_json(
+        "/_synapse/admin/v1/background_updates/status",
+        "reading background update status",
+      )
+      .await?;
+    Ok(BackgroundUpdatesStatus {
+      enabled: raw.enabled,
+      current_updates: raw
+        .current_updates
+        .into_iter()
+        .map(|(database, update)| BackgroundUpdate {
+          database,
+          name: update.name,
+          total_item_count: update.total_item_count,
+          total_duration_ms: update.total_duration_ms,
+          average_items_per_ms: update.average_items_per_ms,
+        })
+        .collect(),
+    })
+  }
+
+  /// Pauses or resumes background updates; returns the state Synapse reports back.
+  pub async fn set_background_updates_enabled(&self, enabled: bool) -> CommandResult<bool> {
+    let response: EnabledResponse = self
+      .send_json(
+        Method::POST,
+        "/_synapse/admin/v1/background_updates/enabled",
+        &json!({ "enabled": enabled }),
+        "toggling background updates",
+      )
+      .await?;
+    Ok(response.enabled)
+  }
+
+  pub async fn start_background_job(&self, job: BackgroundJob, db_name: Option<&str>) -> CommandResult<()> {
+    let mut body = json!({ "job_name": job });
+    if let Some(db_name) = db_name.map(str::trim).filter(|name| !name.is_empty()) {
+      body["db_name"] = json!(db_name);
+    }
+    self
+      .execute(
+        Method::POST,
+        "/_synapse/admin/v1/background_updates/start_job",
+        Some(&body),
+        "starting background job",
+      )
+      .await
+  }
+}
+
+#[tauri::command]
+pub async fn synapse_background_updates_status(
+  app: AppHandle,
+  profile_id: String,
+) -> CommandResult<BackgroundUpdatesStatus> {
+  admin_client(&app, &profile_id).await?.background_updates_status().await
+}
+
+#[tauri::command]
+pub async fn synapse_set_background_updates_enabled(
+  app: AppHandle,
+  profile_id: String,
+  enabled: bool,
+) -> CommandResult<bool> {
+  admin_client(&app, &profile_id).await?.set_background_updates_enabled(enabled).await
+}
+
+#[tauri::command]
+pub async fn synapse_start_background_job(
+  app: AppHandle,
+  profile_id: String,
+  job: BackgroundJob,
+  db_name: Option<String>,
+) -> CommandResult<()> {
+  admin_client(&app, &profile_id).await?.start_background_job(job, db_name.as_deref()).await
+}
@@ -0,0 +1,139 @@
+use super::client::{is_route_missing, SynapseAdminClient};
+use super::de::flexible_bool;
+use super::rooms::DeleteRoute;
+use crate::error::{CommandResult, ErrorKind};
+use crate::now_millis;
+use crate::server_profiles::admin_client;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::AppHandle;
+
+/// Not a legal room id: it has neither the `!` sigil nor a server name.
+///
+/// The probe has to send the real method, because a wrong one cannot tell a
+/// missing DELETE route apart from the v1 `GET /rooms/{id}` that shares its
+/// path. It is still safe: the v2, v1 and legacy POST delete servlets all
+/// check `RoomID.is_valid` and answer 400 before looking up, blocking or
+/// purging anything. No room can ever carry this id, so even a server that
+/// skipped the check would have nothing to act on. Missing routes answer
+/// 404/405 instead.
+const PROBE_ROOM_ID: &str = "fray-capability-probe";
+
+#[derive(Debug, Deserialize)]
+struct WhoamiResponse {
+  user_id: String,
+  /// Absent for appservice and other device-less tokens.
+  #[serde(default)]
+  device_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UserAdminResponse {
+  #[serde(default, deserialize_with = "flexible_bool")]
+  admin: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ServerVersionResponse {
+  #[serde(default)]
+  server_version: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AdminCapabilities {
+  pub user_id: String,
+  pub is_admin: bool,
+  pub server_version: Option<String>,
+  /// Delete routes the server exposes, in the order hard delete tries them.
+  /// Empty when the user is not an admin, since the probes need admin rights.
+  pub delete_routes: Vec<DeleteRoute>,
+  pub checked_at: u64,
+}
+
+impl SynapseAdminClient {
+  pub async fn whoami(&self) -> CommandResult<String> {
+    let response: WhoamiResponse = self
+      .get_json("/_matrix/client/v3/account/whoami", "resolving access token owner")
+      .await?;
+    Ok(response.user_id)
+  }
+
+  /// Device the access token belongs to, if any.
+  pub async fn current_device_id(&self) -> CommandResult<Option<String>> {
+    let response: WhoamiResponse = self
+      .get_json("/_matrix/client/v3/account/whoami", "resolving access token device")
+      .await?;
+    Ok(response.device_id)
+  }
+
+  async fn is_server_admin(&self, user_id: &str) -> CommandResult<bool> {
+    let path = format!("/_synapse/admin/v1/users/{}/admin", urlencoding::encode(user_id));
+    match self.get_json::<UserAdminResponse>(&path, "checking admin status").await {
+      Ok(response) => Ok(response.admin),
+      // Non-admins are refused access to the admin API itself.
+      Err(error) if error.kind == ErrorKind::Forbidden => Ok(false),
+      Err(error) => Err(error),
+    }
+  }
+
+  pub async fn server_version(&self) -> CommandResult<Option<String>> {
+    let response: ServerVersionResponse = self
+      .get_json("/_synapse/admin/v1/server_version", "reading server version")
+      .await?;
+    Ok(response.server_version)
+  }
+
+  async fn probe_delete_route(&self, route: DeleteRoute) -> CommandResult<bool> {
+    let encoded_room_id = urlencoding::encode(PROBE_ROOM_ID);
+    let (method, path) = match route {
+      DeleteRoute::V2 => (Method::DELETE, format!("/_synapse/admin/v2/rooms/{encoded_room_id}")),
+      DeleteRoute::V1 => (Method::DELETE, format!("/_synapse/admin/v1/rooms/{encoded_room_id}")),
+      DeleteRoute::Legacy => (
+        Method::POST,
+        format!("/_synapse/admin/v1/rooms/{encoded_room_id}/delete"),
+      ),
+    };
+    let body = json!({ "block": false, "purge": false });
+    let response = self
+      .send_raw(method, &path, Some(&body), "probing delete routes")
+      .await?;
+    Ok(!is_route_missing(response.status()))
+  }
+
+  pub async fn capabilities(&self) -> CommandResult<AdminCapabilities> {
+    let user_id = self.whoami().await?;
+    let is_admin = self.is_server_admin(&user_id).await?;
+    // The version endpoint is informational; an old or locked-down server
+    // should not hide the rest of the report.
+    let server_version = self.server_version().await.unwrap_or_else(|error| {
+      log::warn!("Unable to read Synapse server version: {error}");
+      None
+    });
+
+    let mut delete_routes = Vec::new();
+    if is_admin {
+      for route in [DeleteRoute::V2, DeleteRoute::V1, DeleteRoute::Legacy] {
+        if self.probe_delete_route(route).await? {
+          delete_routes.push(route);
+        }
+      }
+    }
+
+    Ok(AdminCapabilities {
+      user_id,
+      is_admin,
+      server_version,
+      delete_routes,
+      checked_at: now_millis(),
+    })
+  }
+}
+
+#[tauri::command]
+pub async fn synapse_admin_capabilities(
+  app: AppHandle,
+  profile_id: String,
+) -> CommandResult<AdminCapabilities> {
+  admin_client(&app, &profile_id).await?.capabilities().await
+}
@@ -0,0 +1,146 @@
+use crate::error::{CommandError, CommandResult};
+use reqwest::{header, Client, Method, RequestBuilder, Response, StatusCode};
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::Serialize;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Only connecting is bounded: the synchronous v1 and legacy room delete
+/// routes hold the request open until the purge finishes.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn shared_http_client() -> Client {
+  static CLIENT: OnceLock<Client> = OnceLock::new();
+  CLIENT
+    .get_or_init(|| {
+      Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .build()
+        .unwrap_or_default()
+    })
+    .clone()
+}
+
+pub(crate) fn normalize_base_url(value: &str) -> String {
+  value.trim().trim_end_matches('/').to_string()
+}
+
+/// Appends the non-empty parameters to `path` as a URL-encoded query string.
+pub(crate) fn with_query(path: &str, params: &[(&str, Option<String>)]) -> String {
+  let query: Vec<String> = params
+    .iter()
+    .filter_map(|(key, value)| {
+      value
+        .as_deref()
+        .filter(|value| !value.is_empty())
+        .map(|value| format!("{key}={}", urlencoding::encode(value)))
+    })
+    .collect();
+  if query.is_empty() {
+    path.to_string()
+  } else {
+    format!("{path}?{}", query.join("&"))
+  }
+}
+
+/// Synapse answers unknown admin routes with 404 or 405, which is how older
+/// servers tell us to fall back to a previous API version.
+pub(crate) fn is_route_missing(status: StatusCode) -> bool {
+  status == StatusCode::NOT_FOUND || status == StatusCode::METHOD_NOT_ALLOWED
+}
+
+/// Authenticated handle on a homeserver's `/_synapse/admin` API.
+///
+/// Every instance shares one pooled `reqwest::Client`, so constructing a client
+/// per command is cheap.
+#[derive(Clone)]
+pub struct SynapseAdminClient {
+  http: Client,
+  base_url: String,
+  auth_header: String,
+}
+
+impl SynapseAdminClient {
+  pub fn new(base_url: &str, access_token: &str) -> Self {
+    Self {
+      http: shared_http_client(),
+      base_url: normalize_base_url(base_url),
+      auth_header: format!("Bearer {}", access_token.trim()),
+    }
+  }
+
+  fn request(&self, method: Method, path: &str) -> RequestBuilder {
+    self
+      .http
+      .request(method, format!("{}{}", self.base_url, path))
+      .header(header::AUTHORIZATION, &self.auth_header)
+  }
+
+  async fn send(&self, builder: RequestBuilder, context: &str) -> CommandResult<Response> {
+    builder
+      .send()
+      .await
+      .map_err(|error| CommandError::from_reqwest(error, context))
+  }
+
+  /// Sends a request and returns the raw response, leaving status handling to
+  /// callers that need to probe for route fallbacks.
+  pub async fn send_raw<B: Serialize + ?Sized>(
+    &self,
+    method: Method,
+    path: &str,
+    body: Option<&B>,
+    context: &str,
+  ) -> CommandResult<Response> {
+    let mut builder = self.request(method, path);
+    if let Some(body) = body {
+      builder = builder
+        .header(header::CONTENT_TYPE, "application/json")
+        .json(body);
+    }
+    self.send(builder, context).await
+  }
+
+  pub async fn get_json<T: DeserializeOwned>(&self, path: &str, context: &str) -> CommandResult<T> {
+    let response = self.send(self.request(Method::GET, path), context).await?;
+    Self::read_json(response, context).await
+  }
+
+  pub async fn send_json<B: Serialize + ?Sized, T: DeserializeOwned>(
+    &self,
+    method: Method,
+    path: &str,
+    body: &B,
+    context: &str,
+  ) -> CommandResult<T> {
+    let response = self.send_raw(method, path, Some(body), context).await?;
+    Self::read_json(response, context).await
+  }
+
+  /// Sends a request whose success body carries nothing worth returning.
+  pub async fn execute<B: Serialize + ?Sized>(
+    &self,
+    method: Method,
+    path: &str,
+    body: Option<&B>,
+    context: &str,
+  ) -> CommandResult<()> {
+    let response = self.send_raw(method, path, body, context).await?;
+    Self::read_json::<IgnoredAny>(response, context).await?;
+    Ok(())
+  }
+
+  pub async fn read_json<T: DeserializeOwned>(response: Response, context: &str) -> CommandResult<T> {
+    if !response.status().is_success() {
+      return Err(CommandError::from_response(response).await);
+    }
+    let text = response
+      .text()
+      .await
+      .map_err(|error| CommandError::from_reqwest(error, context))?;
+    // Several admin endpoints reply with an empty body on success.
+    let text = if text.trim().is_empty() { "{}" } else { text.as_str() };
+    serde_json::from_str(text)
+      .map_err(|error| CommandError::parse(format!("Unable to parse Synapse response while {context}: {error}")))
+  }
+}
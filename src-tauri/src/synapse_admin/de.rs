@@ -0,0 +1,30 @@
+//! Deserializers for fields whose JSON type changed between Synapse releases.
+
+use serde::{Deserialize, Deserializer};
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BoolOrInt {
+  Bool(bool),
+  Int(i64),
+}
+
+/// Older Synapse versions report flags such as `admin` and `deactivated` as
+/// `0`/`1` instead of booleans; `null` is read as false.
+pub fn flexible_bool<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+  Ok(match Option::<BoolOrInt>::deserialize(deserializer)? {
+    Some(BoolOrInt::Bool(value)) => value,
+    Some(BoolOrInt::Int(value)) => value != 0,
+    None => false,
+  })
+}
+
+/// Distinguishes an absent field (`None`) from an explicit `null`
+/// (`Some(None)`), for update endpoints where null clears a value.
+pub fn double_option<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+  T: Deserialize<'de>,
+  D: Deserializer<'de>,
+{
+  Option::<T>::deserialize(deserializer).map(Some)
+}
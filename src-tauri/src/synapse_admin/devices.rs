@@ -0,0 +1,303 @@
+use super::client::SynapseAdminClient;
+use super::users::require_user_id;
+use crate::error::{CommandError, CommandResult, ErrorKind};
+use crate::server_profiles::admin_client;
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::AppHandle;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Device {
+  pub device_id: String,
+  #[serde(default)]
+  pub display_name: Option<String>,
+  #[serde(default)]
+  pub last_seen_ip: Option<String>,
+  #[serde(default)]
+  pub last_seen_ts: Option<u64>,
+  /// Only reported by the admin API.
+  #[serde(default)]
+  pub last_seen_user_agent: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceList {
+  #[serde(default)]
+  devices: Vec<Device>,
+}
+
+/// Outcome of deleting the signed-in user's devices. Synapse asks for
+/// user-interactive auth first; resend exactly `device_ids` with `auth`
+/// completed for `session`, since the auth only covers the request it was
+/// issued for. A rejected attempt comes back as `auth_required` again, with
+/// `errcode` (e.g. `M_FORBIDDEN` for a wrong password) and `error` set.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum OwnDeviceDeletion {
+  Deleted {
+    device_ids: Vec<String>,
+  },
+  AuthRequired {
+    /// Devices the challenge was issued for.
+    device_ids: Vec<String>,
+    session: Option<String>,
+    flows: Value,
+    params: Value,
+    /// Stages already passed in this session.
+    completed: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    errcode: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+  },
+}
+
+#[derive(Debug, Deserialize)]
+struct InteractiveAuthChallenge {
+  #[serde(default)]
+  session: Option<String>,
+  flows: Value,
+  #[serde(default)]
+  params: Value,
+  #[serde(default)]
+  completed: Vec<String>,
+  #[serde(default)]
+  errcode: Option<String>,
+  #[serde(default)]
+  error: Option<String>,
+}
+
+/// Devices not seen since `older_than_ts` (or never seen), minus `keep`.
+fn stale_device_ids(devices: Vec<Device>, older_than_ts: u64, keep: &[String]) -> Vec<String> {
+  devices
+    .into_iter()
+    .filter(|device| device.last_seen_ts.map_or(true, |seen| seen < older_than_ts))
+    .map(|device| device.device_id)
+    .filter(|device_id| !keep.contains(device_id))
+    .collect()
+}
+
+fn admin_devices_path(user_id: &str, suffix: &str) -> CommandResult<String> {
+  Ok(format!(
+    "/_synapse/admin/v2/users/{}{suffix}",
+    urlencoding::encode(require_user_id(user_id)?)
+  ))
+}
+
+fn require_device_id(device_id: &str) -> CommandResult<&str> {
+  let device_id = device_id.trim();
+  if device_id.is_empty() {
+    return Err(CommandError::invalid_input("A device id is required."));
+  }
+  Ok(device_id)
+}
+
+impl SynapseAdminClient {
+  pub async fn user_devices(&self, user_id: &str) -> CommandResult<Vec<Device>> {
+    let list: DeviceList = self
+      .get_json(&admin_devices_path(user_id, "/devices")?, "listing user devices")
+      .await?;
+    Ok(list.devices)
+  }
+
+  pub async fn rename_user_device(&self, user_id: &str, device_id: &str, display_name: &str) -> CommandResult<()> {
+    let path = admin_devices_path(
+      user_id,
+      &format!("/devices/{}", urlencoding::encode(require_device_id(device_id)?)),
+    )?;
+    self
+      .execute(
+        Method::PUT,
+        &path,
+        Some(&json!({ "display_name": display_name })),
+        "renaming device",
+      )
+      .await
+  }
+
+  pub async fn delete_user_devices(&self, user_id: &str, device_ids: &[String]) -> CommandResult<()> {
+    if device_ids.is_empty() {
+      return Ok(());
+    }
+    self
+      .execute(
+        Method::POST,
+        &admin_devices_path(user_id, "/delete_devices")?,
+        Some(&json!({ "devices": device_ids })),
+        "deleting devices",
+      )
+      .await
+  }
+
+  pub async fn own_devices(&self) -> CommandResult<Vec<Device>> {
+    let list: DeviceList = self
+      .get_json("/_matrix/client/v3/devices", "listing devices")
+      .await?;
+    Ok(list.devices)
+  }
+
+  pub async fn rename_own_device(&self, device_id: &str, display_name: &str) -> CommandResult<()> {
+    let path = format!(
+      "/_matrix/client/v3/devices/{}",
+      urlencoding::encode(require_device_id(device_id)?)
+    );
+    self
+      .execute(
+        Method::PUT,
+        &path,
+        Some(&json!({ "display_name": display_name })),
+        "renaming device",
+      )
+      .await
+  }
+
+  pub async fn delete_own_devices(&self, device_ids: &[String], auth: Option<&Value>) -> CommandResult<OwnDeviceDeletion> {
+    let mut body = json!({ "devices": device_ids });
+    if let Some(auth) = auth {
+      body["auth"] = auth.clone();
+    }
+    let response = self
+      .send_raw(
+        Method::POST,
+        "/_matrix/client/v3/delete_devices",
+        Some(&body),
+        "deleting devices",
+      )
+      .await?;
+    if response.status() == StatusCode::UNAUTHORIZED {
+      let text = response.text().await.unwrap_or_default();
+      return match serde_json::from_str::<InteractiveAuthChallenge>(&text) {
+        Ok(challenge) => Ok(OwnDeviceDeletion::AuthRequired {
+          device_ids: device_ids.to_vec(),
+          session: challenge.session,
+          flows: challenge.flows,
+          params: challenge.params,
+          completed: challenge.completed,
+          errcode: challenge.errcode,
+          error: challenge.error,
+        }),
+        Err(_) => Err(CommandError::new(
+          ErrorKind::Auth,
+          format!("401 Unauthorized: {text}"),
+        )),
+      };
+    }
+    Self::read_json::<Value>(response, "deleting devices").await?;
+    Ok(OwnDeviceDeletion::Deleted {
+      device_ids: device_ids.to_vec(),
+    })
+  }
+}
+
+#[tauri::command]
+pub async fn synapse_list_user_devices(
+  app: AppHandle,
+  profile_id: String,
+  user_id: String,
+) -> CommandResult<Vec<Device>> {
+  admin_client(&app, &profile_id).await?.user_devices(&user_id).await
+}
+
+#[tauri::command]
+pub async fn synapse_rename_user_device(
+  app: AppHandle,
+  profile_id: String,
+  user_id: String,
+  device_id: String,
+  display_name: String,
+) -> CommandResult<()> {
+  admin_client(&app, &profile_id)
+    .await?
+    .rename_user_device(&user_id, &device_id, &display_name)
+    .await
+}
+
+#[tauri::command]
+pub async fn synapse_delete_user_devices(
+  app: AppHandle,
+  profile_id: String,
+  user_id: String,
+  device_ids: Vec<String>,
+) -> CommandResult<()> {
+  admin_client(&app, &profile_id).await?.delete_user_devices(&user_id, &device_ids).await
+}
+
+/// Deletes every device of `user_id` last seen before `older_than_ts`, except
+/// `keep_device_ids`. Returns the deleted device ids.
+#[tauri::command]
+pub async fn synapse_delete_stale_user_devices(
+  app: AppHandle,
+  profile_id: String,
+  user_id: String,
+  older_than_ts: u64,
+  keep_device_ids: Option<Vec<String>>,
+) -> CommandResult<Vec<String>> {
+  let client = admin_client(&app, &profile_id).await?;
+  let stale = stale_device_ids(
+    client.user_devices(&user_id).await?,
+    older_than_ts,
+    &keep_device_ids.unwrap_or_default(),
+  );
+  client.delete_user_devices(&user_id, &stale).await?;
+  Ok(stale)
+}
+
+#[tauri::command]
+pub async fn matrix_list_own_devices(app: AppHandle, profile_id: String) -> CommandResult<Vec<Device>> {
+  admin_client(&app, &profile_id).await?.own_devices().await
+}
+
+#[tauri::command]
+pub async fn matrix_rename_own_device(
+  app: AppHandle,
+  profile_id: String,
+  device_id: String,
+  display_name: String,
+) -> CommandResult<()> {
+  admin_client(&app, &profile_id).await?.rename_own_device(&device_id, &display_name).await
+}
+
+#[tauri::command]
+pub async fn matrix_delete_own_devices(
+  app: AppHandle,
+  profile_id: String,
+  device_ids: Vec<String>,
+  auth: Option<Value>,
+) -> CommandResult<OwnDeviceDeletion> {
+  admin_client(&app, &profile_id).await?.delete_own_devices(&device_ids, auth.as_ref()).await
+}
+
+/// Signed-in variant of `synapse_delete_stale_user_devices`. The device the
+/// profile's token belongs to is always kept. The first call computes the
+/// stale list; when it returns `auth_required`, call again with `auth` and the
+/// challenge's `device_ids`, which are resent as-is instead of recomputed.
+#[tauri::command]
+pub async fn matrix_delete_stale_own_devices(
+  app: AppHandle,
+  profile_id: String,
+  older_than_ts: u64,
+  keep_device_ids: Option<Vec<String>>,
+  device_ids: Option<Vec<String>>,
+  auth: Option<Value>,
+) -> CommandResult<OwnDeviceDeletion> {
+  let client = admin_client(&app, &profile_id).await?;
+  let mut keep = keep_device_ids.unwrap_or_default();
+  keep.extend(client.current_device_id().await?);
+  let stale = match (auth.is_some(), device_ids) {
+    (true, Some(device_ids)) => device_ids
+      .into_iter()
+      .filter(|device_id| !keep.contains(device_id))
+      .collect(),
+    (true, None) => {
+      return Err(CommandError::invalid_input(
+        "Resend the device_ids from the auth challenge together with auth.",
+      ))
+    }
+    (false, _) => stale_device_ids(client.own_devices().await?, older_than_ts, &keep),
+  };
+  if stale.is_empty() {
+    return Ok(OwnDeviceDeletion::Deleted { device_ids: stale });
+  }
+  client.delete_own_devices(&stale, auth.as_ref()).await
+}
@@ -0,0 +1,130 @@
+use super::client::{with_query, SynapseAdminClient};
+use crate::error::CommandResult;
+use crate::server_profiles::admin_client;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::AppHandle;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EventReportQuery {
+  #[serde(default)]
+  pub from: Option<u64>,
+  #[serde(default)]
+  pub limit: Option<u32>,
+  /// `b` (newest first, the Synapse default) or `f`.
+  #[serde(default)]
+  pub dir: Option<String>,
+  #[serde(default)]
+  pub room_id: Option<String>,
+  /// Only reports filed by this user.
+  #[serde(default)]
+  pub user_id: Option<String>,
+  /// Only reports about events sent by this user.
+  #[serde(default)]
+  pub event_sender_user_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventReport {
+  pub id: u64,
+  #[serde(default)]
+  pub received_ts: u64,
+  pub room_id: String,
+  pub event_id: String,
+  /// The reporting user.
+  pub user_id: String,
+  #[serde(default)]
+  pub reason: Option<String>,
+  #[serde(default)]
+  pub score: Option<i64>,
+  /// Sender of the reported event.
+  #[serde(default)]
+  pub sender: Option<String>,
+  /// Room name at the time of listing.
+  #[serde(default)]
+  pub name: Option<String>,
+  #[serde(default)]
+  pub canonical_alias: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventReportPage {
+  #[serde(default)]
+  pub event_reports: Vec<EventReport>,
+  #[serde(default)]
+  pub next_token: Option<u64>,
+  #[serde(default)]
+  pub total: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventReportDetails {
+  #[serde(flatten)]
+  pub report: EventReport,
+  /// The full reported event as stored by Synapse.
+  #[serde(default)]
+  pub event_json: Value,
+}
+
+impl SynapseAdminClient {
+  pub async fn list_event_reports(&self, query: &EventReportQuery) -> CommandResult<EventReportPage> {
+    let path = with_query(
+      "/_synapse/admin/v1/event_reports",
+      &[
+        ("from", query.from.map(|from| from.to_string())),
+        ("limit", query.limit.map(|limit| limit.to_string())),
+        ("dir", query.dir.clone()),
+        ("room_id", query.room_id.clone()),
+        ("user_id", query.user_id.clone()),
+        ("event_sender_user_id", query.event_sender_user_id.clone()),
+      ],
+    );
+    self.get_json(&path, "listing event reports").await
+  }
+
+  pub async fn event_report(&self, report_id: u64) -> CommandResult<EventReportDetails> {
+    self
+      .get_json(
+        &format!("/_synapse/admin/v1/event_reports/{report_id}"),
+        "loading event report",
+      )
+      .await
+  }
+
+  pub async fn delete_event_report(&self, report_id: u64) -> CommandResult<()> {
+    self
+      .execute::<()>(
+        Method::DELETE,
+        &format!("/_synapse/admin/v1/event_reports/{report_id}"),
+        None,
+        "deleting event report",
+      )
+      .await
+  }
+}
+
+#[tauri::command]
+pub async fn synapse_list_event_reports(
+  app: AppHandle,
+  profile_id: String,
+  query: Option<EventReportQuery>,
+) -> CommandResult<EventReportPage> {
+  admin_client(&app, &profile_id).await?.list_event_reports(&query.unwrap_or_default()).await
+}
+
+#[tauri::command]
+pub async fn synapse_get_event_report(
+  app: AppHandle,
+  profile_id: String,
+  report_id: u64,
+) -> CommandResult<EventReportDetails> {
+  admin_client(&app, &profile_id).await?.event_report(report_id).await
+}
+
+/// Synapse has no "resolved" state for reports, so resolving one from the
+/// moderation queue deletes it.
+#[tauri::command]
+pub async fn synapse_delete_event_report(app: AppHandle, profile_id: String, report_id: u64) -> CommandResult<()> {
+  admin_client(&app, &profile_id).await?.delete_event_report(report_id).await
+}
@@ -0,0 +1,180 @@
+use super::client::{with_query, SynapseAdminClient};
+use crate::error::{CommandError, CommandResult};
+use crate::server_profiles::admin_client;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+fn destination_path(destination: &str, suffix: &str) -> CommandResult<String> {
+  let destination = destination.trim();
+  if destination.is_empty() || destination.contains('/') {
+    return Err(CommandError::invalid_input(format!(
+      "\"{destination}\" is not a valid server name."
+    )));
+  }
+  Ok(format!(
+    "/_synapse/admin/v1/federation/destinations/{}{suffix}",
+    urlencoding::encode(destination)
+  ))
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DestinationQuery {
+  #[serde(default)]
+  pub from: Option<String>,
+  #[serde(default)]
+  pub limit: Option<u32>,
+  /// e.g. `destination`, `retry_last_ts`, `retry_interval`, `failure_ts`.
+  #[serde(default)]
+  pub order_by: Option<String>,
+  #[serde(default)]
+  pub dir: Option<String>,
+  /// Substring filter on the server name.
+  #[serde(default)]
+  pub destination: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FederationDestination {
+  pub destination: String,
+  /// When the last retry happened, 0 if never.
+  #[serde(default)]
+  pub retry_last_ts: u64,
+  /// Current backoff in milliseconds; 0 when the destination is healthy.
+  #[serde(default)]
+  pub retry_interval: u64,
+  /// Start of the current failure streak, if any.
+  #[serde(default)]
+  pub failure_ts: Option<u64>,
+  #[serde(default)]
+  pub last_successful_stream_ordering: Option<u64>,
+  /// Earliest time Synapse will try again, derived locally.
+  #[serde(default)]
+  pub next_retry_ts: Option<u64>,
+}
+
+impl FederationDestination {
+  fn with_next_retry(mut self) -> Self {
+    self.next_retry_ts = (self.retry_interval > 0).then(|| self.retry_last_ts + self.retry_interval);
+    self
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DestinationPage {
+  #[serde(default)]
+  pub destinations: Vec<FederationDestination>,
+  #[serde(default)]
+  pub next_token: Option<String>,
+  #[serde(default)]
+  pub total: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DestinationRoom {
+  pub room_id: String,
+  #[serde(default)]
+  pub stream_ordering: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DestinationRoomPage {
+  #[serde(default)]
+  pub rooms: Vec<DestinationRoom>,
+  #[serde(default)]
+  pub next_token: Option<String>,
+  #[serde(default)]
+  pub total: u64,
+}
+
+impl SynapseAdminClient {
+  pub async fn list_destinations(&self, query: &DestinationQuery) -> CommandResult<DestinationPage> {
+    let path = with_query(
+      "/_synapse/admin/v1/federation/destinations",
+      &[
+        ("from", query.from.clone()),
+        ("limit", query.limit.map(|limit| limit.to_string())),
+        ("order_by", query.order_by.clone()),
+        ("dir", query.dir.clone()),
+        ("destination", query.destination.clone()),
+      ],
+    );
+    let mut page: DestinationPage = self.get_json(&path, "listing federation destinations").await?;
+    page.destinations = page
+      .destinations
+      .into_iter()
+      .map(FederationDestination::with_next_retry)
+      .collect();
+    Ok(page)
+  }
+
+  pub async fn destination(&self, destination: &str) -> CommandResult<FederationDestination> {
+    let details: FederationDestination = self
+      .get_json(&destination_path(destination, "")?, "loading federation destination")
+      .await?;
+    Ok(details.with_next_retry())
+  }
+
+  pub async fn destination_rooms(
+    &self,
+    destination: &str,
+    from: Option<String>,
+    limit: Option<u32>,
+  ) -> CommandResult<DestinationRoomPage> {
+    let path = with_query(
+      &destination_path(destination, "/rooms")?,
+      &[("from", from), ("limit", limit.map(|limit| limit.to_string()))],
+    );
+    self.get_json(&path, "listing destination rooms").await
+  }
+
+  /// Clears the retry backoff so Synapse contacts the destination immediately.
+  pub async fn reset_destination_connection(&self, destination: &str) -> CommandResult<()> {
+    self
+      .execute::<()>(
+        Method::POST,
+        &destination_path(destination, "/reset_connection")?,
+        None,
+        "resetting destination backoff",
+      )
+      .await
+  }
+}
+
+#[tauri::command]
+pub async fn synapse_list_federation_destinations(
+  app: AppHandle,
+  profile_id: String,
+  query: Option<DestinationQuery>,
+) -> CommandResult<DestinationPage> {
+  admin_client(&app, &profile_id).await?.list_destinations(&query.unwrap_or_default()).await
+}
+
+#[tauri::command]
+pub async fn synapse_get_federation_destination(
+  app: AppHandle,
+  profile_id: String,
+  destination: String,
+) -> CommandResult<FederationDestination> {
+  admin_client(&app, &profile_id).await?.destination(&destination).await
+}
+
+#[tauri::command]
+pub async fn synapse_list_destination_rooms(
+  app: AppHandle,
+  profile_id: String,
+  destination: String,
+  from: Option<String>,
+  limit: Option<u32>,
+) -> CommandResult<DestinationRoomPage> {
+  admin_client(&app, &profile_id).await?.destination_rooms(&destination, from, limit).await
+}
+
+#[tauri::command]
+pub async fn synapse_reset_destination_backoff(
+  app: AppHandle,
+  profile_id: String,
+  destination: String,
+) -> CommandResult<()> {
+  admin_client(&app, &profile_id).await?.reset_destination_connection(&destination).await
+}
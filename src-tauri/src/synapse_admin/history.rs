@@ -0,0 +1,166 @@
+use super::client::SynapseAdminClient;
+use super::room_inventory::require_room_id;
+use crate::error::{CommandError, CommandResult, ErrorKind};
+use crate::server_profiles::admin_client;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tokio::time::sleep;
+
+const PURGE_HISTORY_PROGRESS_EVENT: &str = "purge-history-progress";
+/// History purges of busy rooms run much longer than room deletes.
+const PURGE_HISTORY_WAIT_TIMEOUT: Duration = Duration::from_secs(600);
+const PURGE_HISTORY_POLL_INTERVAL: Duration = Duration::from_millis(1500);
+
+/// Where to cut history. Exactly one of the two bounds must be set.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PurgeHistoryOptions {
+  #[serde(default)]
+  pub purge_up_to_ts: Option<u64>,
+  #[serde(default)]
+  pub purge_up_to_event_id: Option<String>,
+  /// Also delete events sent by local users. Synapse keeps them by default.
+  #[serde(default)]
+  pub delete_local_events: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct PurgeHistoryRequest {
+  delete_local_events: bool,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  purge_up_to_ts: Option<u64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  purge_up_to_event_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PurgeHistoryResponse {
+  purge_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurgeHistoryStatus {
+  /// `active`, `complete` or `failed`.
+  pub status: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+struct PurgeHistoryProgressEvent<'a> {
+  room_id: &'a str,
+  purge_id: &'a str,
+  status: &'a str,
+}
+
+impl SynapseAdminClient {
+  /// Starts a history purge and returns its `purge_id`.
+  pub async fn start_purge_history(&self, room_id: &str, options: &PurgeHistoryOptions) -> CommandResult<String> {
+    let room_id = require_room_id(room_id)?;
+    let event_id = options
+      .purge_up_to_event_id
+      .as_deref()
+      .map(str::trim)
+      .filter(|event_id| !event_id.is_empty());
+    if event_id.is_some() == options.purge_up_to_ts.is_some() {
+      return Err(CommandError::invalid_input(
+        "Set exactly one of purge_up_to_ts or purge_up_to_event_id.",
+      ));
+    }
+    let request = PurgeHistoryRequest {
+      delete_local_events: options.delete_local_events,
+      purge_up_to_ts: options.purge_up_to_ts,
+      purge_up_to_event_id: event_id.map(ToString::to_string),
+    };
+    let response: PurgeHistoryResponse = self
+      .send_json(
+        Method::POST,
+        &format!("/_synapse/admin/v1/purge_history/{}", urlencoding::encode(room_id)),
+        &request,
+        "purging room history",
+      )
+      .await?;
+    Ok(response.purge_id)
+  }
+
+  pub async fn purge_history_status(&self, purge_id: &str) -> CommandResult<PurgeHistoryStatus> {
+    self
+      .get_json(
+        &format!("/_synapse/admin/v1/purge_history_status/{}", urlencoding::encode(purge_id)),
+        "polling purge history status",
+      )
+      .await
+  }
+
+  /// Polls until the purge completes, calling `on_status` whenever the
+  /// reported status changes.
+  pub async fn wait_for_purge_history(
+    &self,
+    purge_id: &str,
+    on_status: &(dyn Fn(&str) + Send + Sync),
+  ) -> CommandResult<()> {
+    let started = Instant::now();
+    let mut last_status: Option<String> = None;
+    while started.elapsed() < PURGE_HISTORY_WAIT_TIMEOUT {
+      let current = self.purge_history_status(purge_id).await?;
+      if last_status.as_deref() != Some(current.status.as_str()) {
+        on_status(&current.status);
+        last_status = Some(current.status.clone());
+      }
+      if current.status.eq_ignore_ascii_case("complete") {
+        return Ok(());
+      }
+      if current.status.eq_ignore_ascii_case("failed") {
+        return Err(CommandError::new(
+          ErrorKind::Synapse,
+          current
+            .error
+            .filter(|message| !message.trim().is_empty())
+            .unwrap_or_else(|| "Synapse history purge failed.".to_string()),
+        ));
+      }
+
+      sleep(PURGE_HISTORY_POLL_INTERVAL).await;
+    }
+
+    Err(CommandError::new(
+      ErrorKind::Timeout,
+      format!("Timed out waiting for history purge {purge_id}; Synapse may still be running it."),
+    ))
+  }
+}
+
+/// Purges room history and waits for it to finish, emitting a
+/// `purge-history-progress` event on each status change. Returns the `purge_id`.
+#[tauri::command]
+pub async fn synapse_purge_room_history(
+  app: AppHandle,
+  profile_id: String,
+  room_id: String,
+  options: PurgeHistoryOptions,
+) -> CommandResult<String> {
+  let client = admin_client(&app, &profile_id).await?;
+  let purge_id = client.start_purge_history(&room_id, &options).await?;
+  let on_status = |status: &str| {
+    let event = PurgeHistoryProgressEvent {
+      room_id: &room_id,
+      purge_id: &purge_id,
+      status,
+    };
+    if let Err(error) = app.emit(PURGE_HISTORY_PROGRESS_EVENT, event) {
+      log::warn!("Failed to emit purge history progress: {error}");
+    }
+  };
+  client.wait_for_purge_history(&purge_id, &on_status).await?;
+  Ok(purge_id)
+}
+
+#[tauri::command]
+pub async fn synapse_purge_history_status(
+  app: AppHandle,
+  profile_id: String,
+  purge_id: String,
+) -> CommandResult<PurgeHistoryStatus> {
+  admin_client(&app, &profile_id).await?.purge_history_status(&purge_id).await
+}
@@ -0,0 +1,326 @@
+use super::client::{with_query, SynapseAdminClient};
+use super::de::flexible_bool;
+use super::room_inventory::require_room_id;
+use super::users::require_user_id;
+use crate::error::{CommandError, CommandResult};
+use crate::now_millis;
+use crate::server_profiles::admin_client;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+/// Splits `mxc://server/media_id` into its server name and media id.
+fn parse_mxc(uri: &str) -> CommandResult<(&str, &str)> {
+  uri
+    .trim()
+    .strip_prefix("mxc://")
+    .and_then(|rest| rest.split_once('/'))
+    .filter(|(server_name, media_id)| !server_name.is_empty() && !media_id.is_empty() && !media_id.contains('/'))
+    .ok_or_else(|| CommandError::invalid_input(format!("\"{uri}\" is not a valid mxc:// URI.")))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomMedia {
+  #[serde(default)]
+  pub local: Vec<String>,
+  #[serde(default)]
+  pub remote: Vec<String>,
+}
+
+/// Paging shared by the per-user media listing and the media usage report.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MediaPageQuery {
+  #[serde(default)]
+  pub from: Option<u64>,
+  #[serde(default)]
+  pub limit: Option<u32>,
+  /// `media_length`, `created_ts`, `media_count`, ... depending on the endpoint.
+  #[serde(default)]
+  pub order_by: Option<String>,
+  #[serde(default)]
+  pub dir: Option<String>,
+}
+
+impl MediaPageQuery {
+  fn params(&self) -> Vec<(&'static str, Option<String>)> {
+    vec![
+      ("from", self.from.map(|from| from.to_string())),
+      ("limit", self.limit.map(|limit| limit.to_string())),
+      ("order_by", self.order_by.clone()),
+      ("dir", self.dir.clone()),
+    ]
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaItem {
+  pub media_id: String,
+  #[serde(default)]
+  pub media_type: Option<String>,
+  #[serde(default)]
+  pub media_length: u64,
+  #[serde(default)]
+  pub upload_name: Option<String>,
+  #[serde(default)]
+  pub created_ts: Option<u64>,
+  #[serde(default)]
+  pub last_access_ts: Option<u64>,
+  #[serde(default)]
+  pub quarantined_by: Option<String>,
+  #[serde(default, deserialize_with = "flexible_bool")]
+  pub safe_from_quarantine: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserMediaPage {
+  #[serde(default)]
+  pub media: Vec<MediaItem>,
+  #[serde(default)]
+  pub next_token: Option<u64>,
+  #[serde(default)]
+  pub total: u64,
+  /// Sum of `media_length` over this page, filled in locally.
+  #[serde(default)]
+  pub page_bytes: u64,
+}
+
+/// What to quarantine: one media item, everything in a room, or everything a
+/// user uploaded.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum QuarantineTarget {
+  Media { mxc: String },
+  Room { room_id: String },
+  User { user_id: String },
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QuarantineResult {
+  /// Not reported for single-item quarantine, which always affects one item.
+  #[serde(default)]
+  pub num_quarantined: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeletedMedia {
+  #[serde(default)]
+  pub deleted_media: Vec<String>,
+  #[serde(default)]
+  pub total: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurgedMediaCache {
+  #[serde(default)]
+  pub deleted: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserMediaUsage {
+  pub user_id: String,
+  #[serde(default)]
+  pub displayname: Option<String>,
+  #[serde(default)]
+  pub media_count: u64,
+  #[serde(default)]
+  pub media_length: u64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MediaUsageQuery {
+  #[serde(flatten)]
+  pub page: MediaPageQuery,
+  #[serde(default)]
+  pub from_ts: Option<u64>,
+  #[serde(default)]
+  pub until_ts: Option<u64>,
+  #[serde(default)]
+  pub search_term: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaUsageReport {
+  #[serde(default)]
+  pub users: Vec<UserMediaUsage>,
+  #[serde(default)]
+  pub next_token: Option<u64>,
+  #[serde(default)]
+  pub total: u64,
+  /// Sum of `media_length` over this page, filled in locally.
+  #[serde(default)]
+  pub page_bytes: u64,
+}
+
+impl SynapseAdminClient {
+  pub async fn room_media(&self, room_id: &str) -> CommandResult<RoomMedia> {
+    let path = format!(
+      "/_synapse/admin/v1/room/{}/media",
+      urlencoding::encode(require_room_id(room_id)?)
+    );
+    self.get_json(&path, "listing room media").await
+  }
+
+  pub async fn user_media(&self, user_id: &str, query: &MediaPageQuery) -> CommandResult<UserMediaPage> {
+    let path = with_query(
+      &format!(
+        "/_synapse/admin/v1/users/{}/media",
+        urlencoding::encode(require_user_id(user_id)?)
+      ),
+      &query.params(),
+    );
+    let mut page: UserMediaPage = self.get_json(&path, "listing user media").await?;
+    page.page_bytes = page.media.iter().map(|item| item.media_length).sum();
+    Ok(page)
+  }
+
+  pub async fn quarantine_media(&self, target: &QuarantineTarget) -> CommandResult<QuarantineResult> {
+    let (path, expect_count) = match target {
+      QuarantineTarget::Media { mxc } => {
+        let (server_name, media_id) = parse_mxc(mxc)?;
+        (
+          format!(
+            "/_synapse/admin/v1/media/quarantine/{}/{}",
+            urlencoding::encode(server_name),
+            urlencoding::encode(media_id)
+          ),
+          false,
+        )
+      }
+      QuarantineTarget::Room { room_id } => (
+        format!(
+          "/_synapse/admin/v1/room/{}/media/quarantine",
+          urlencoding::encode(require_room_id(room_id)?)
+        ),
+        true,
+      ),
+      QuarantineTarget::User { user_id } => (
+        format!(
+          "/_synapse/admin/v1/user/{}/media/quarantine",
+          urlencoding::encode(require_user_id(user_id)?)
+        ),
+        true,
+      ),
+    };
+    let response = self
+      .send_raw::<()>(Method::POST, &path, None, "quarantining media")
+      .await?;
+    let mut result: QuarantineResult = Self::read_json(response, "quarantining media").await?;
+    if !expect_count {
+      result.num_quarantined = 1;
+    }
+    Ok(result)
+  }
+
+  /// Deletes local media last accessed before `before_ts`, optionally only
+  /// files larger than `size_gt` bytes.
+  pub async fn delete_local_media(
+    &self,
+    before_ts: u64,
+    size_gt: Option<u64>,
+    keep_profiles: bool,
+  ) -> CommandResult<DeletedMedia> {
+    let path = with_query(
+      "/_synapse/admin/v1/media/delete",
+      &[
+        ("before_ts", Some(before_ts.to_string())),
+        ("size_gt", size_gt.map(|size| size.to_string())),
+        ("keep_profiles", Some(keep_profiles.to_string())),
+      ],
+    );
+    let response = self
+      .send_raw::<()>(Method::POST, &path, None, "deleting local media")
+      .await?;
+    Self::read_json(response, "deleting local media").await
+  }
+
+  pub async fn purge_remote_media_cache(&self, before_ts: u64) -> CommandResult<PurgedMediaCache> {
+    let path = with_query(
+      "/_synapse/admin/v1/purge_media_cache",
+      &[("before_ts", Some(before_ts.to_string()))],
+    );
+    let response = self
+      .send_raw::<()>(Method::POST, &path, None, "purging remote media cache")
+      .await?;
+    Self::read_json(response, "purging remote media cache").await
+  }
+
+  pub async fn media_usage(&self, query: &MediaUsageQuery) -> CommandResult<MediaUsageReport> {
+    let mut params = query.page.params();
+    params.push(("from_ts", query.from_ts.map(|ts| ts.to_string())));
+    params.push(("until_ts", query.until_ts.map(|ts| ts.to_string())));
+    params.push(("search_term", query.search_term.clone()));
+    let path = with_query("/_synapse/admin/v1/statistics/users/media", &params);
+    let mut report: MediaUsageReport = self.get_json(&path, "loading media usage").await?;
+    report.page_bytes = report.users.iter().map(|user| user.media_length).sum();
+    Ok(report)
+  }
+}
+
+#[tauri::command]
+pub async fn synapse_list_room_media(app: AppHandle, profile_id: String, room_id: String) -> CommandResult<RoomMedia> {
+  admin_client(&app, &profile_id).await?.room_media(&room_id).await
+}
+
+#[tauri::command]
+pub async fn synapse_list_user_media(
+  app: AppHandle,
+  profile_id: String,
+  user_id: String,
+  query: Option<MediaPageQuery>,
+) -> CommandResult<UserMediaPage> {
+  admin_client(&app, &profile_id).await?.user_media(&user_id, &query.unwrap_or_default()).await
+}
+
+#[tauri::command]
+pub async fn synapse_quarantine_media(
+  app: AppHandle,
+  profile_id: String,
+  target: QuarantineTarget,
+) -> CommandResult<QuarantineResult> {
+  admin_client(&app, &profile_id).await?.quarantine_media(&target).await
+}
+
+/// At least one of `before_ts` and a non-zero `size_gt` is required, so an
+/// empty call never wipes every local file. `before_ts` defaults to now, so
+/// passing only `size_gt` deletes every local file above that size.
+#[tauri::command]
+pub async fn synapse_delete_local_media(
+  app: AppHandle,
+  profile_id: String,
+  before_ts: Option<u64>,
+  size_gt: Option<u64>,
+  keep_profiles: Option<bool>,
+) -> CommandResult<DeletedMedia> {
+  let size_gt = size_gt.filter(|size| *size > 0);
+  if before_ts.is_none() && size_gt.is_none() {
+    return Err(CommandError::invalid_input(
+      "Deleting local media requires before_ts or a non-zero size_gt.",
+    ));
+  }
+  admin_client(&app, &profile_id)
+    .await?
+    .delete_local_media(
+      before_ts.unwrap_or_else(now_millis),
+      size_gt,
+      keep_profiles.unwrap_or(true),
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn synapse_purge_remote_media_cache(
+  app: AppHandle,
+  profile_id: String,
+  before_ts: u64,
+) -> CommandResult<PurgedMediaCache> {
+  admin_client(&app, &profile_id).await?.purge_remote_media_cache(before_ts).await
+}
+
+#[tauri::command]
+pub async fn synapse_media_usage(
+  app: AppHandle,
+  profile_id: String,
+  query: Option<MediaUsageQuery>,
+) -> CommandResult<MediaUsageReport> {
+  admin_client(&app, &profile_id).await?.media_usage(&query.unwrap_or_default()).await
+}
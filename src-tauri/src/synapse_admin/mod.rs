@@ -0,0 +1,22 @@
+//! Typed access to the Synapse admin API.
+//!
+//! All admin commands go through [`SynapseAdminClient`], which owns URL
+//! building, bearer auth and the shared connection pool.
+
+pub mod background_updates;
+pub mod capabilities;
+mod client;
+pub(crate) mod de;
+pub mod devices;
+pub mod event_reports;
+pub mod federation;
+pub mod history;
+pub mod media;
+pub mod registration_tokens;
+pub mod room_inventory;
+pub mod rooms;
+pub mod server_notices;
+pub mod users;
+
+pub(crate) use client::normalize_base_url;
+pub use client::SynapseAdminClient;
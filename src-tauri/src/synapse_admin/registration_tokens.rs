@@ -0,0 +1,167 @@
+use super::client::{with_query, SynapseAdminClient};
+use super::de::double_option;
+use crate::error::{CommandError, CommandResult};
+use crate::server_profiles::admin_client;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+const TOKENS_PATH: &str = "/_synapse/admin/v1/registration_tokens";
+const MAX_TOKEN_LENGTH: usize = 64;
+
+fn validate_token(token: &str) -> CommandResult<&str> {
+  let token = token.trim();
+  let allowed = |c: char| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '~' | '-');
+  if token.is_empty() || token.len() > MAX_TOKEN_LENGTH || !token.chars().all(allowed) {
+    return Err(CommandError::invalid_input(
+      "Registration tokens are 1-64 characters of A-Z, a-z, 0-9, '.', '_', '~' or '-'.",
+    ));
+  }
+  Ok(token)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistrationToken {
+  pub token: String,
+  /// `None` means unlimited uses.
+  #[serde(default)]
+  pub uses_allowed: Option<u64>,
+  /// Registrations started with this token that have not completed yet.
+  #[serde(default)]
+  pub pending: u64,
+  #[serde(default)]
+  pub completed: u64,
+  /// Milliseconds since the epoch; `None` means the token never expires.
+  #[serde(default)]
+  pub expiry_time: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RegistrationTokenList {
+  #[serde(default)]
+  registration_tokens: Vec<RegistrationToken>,
+}
+
+/// Options for a new token. Leave `token` unset to let Synapse generate one of
+/// `length` characters (16 by default).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NewRegistrationToken {
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub token: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub uses_allowed: Option<u64>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub expiry_time: Option<u64>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub length: Option<usize>,
+}
+
+/// Fields to change on an existing token. An omitted field is left alone and
+/// an explicit `null` removes the limit.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RegistrationTokenUpdate {
+  #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+  pub uses_allowed: Option<Option<u64>>,
+  #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+  pub expiry_time: Option<Option<u64>>,
+}
+
+impl SynapseAdminClient {
+  /// Lists tokens; `valid` filters to usable (`true`) or exhausted/expired (`false`) ones.
+  pub async fn list_registration_tokens(&self, valid: Option<bool>) -> CommandResult<Vec<RegistrationToken>> {
+    let path = with_query(TOKENS_PATH, &[("valid", valid.map(|valid| valid.to_string()))]);
+    let list: RegistrationTokenList = self.get_json(&path, "listing registration tokens").await?;
+    Ok(list.registration_tokens)
+  }
+
+  pub async fn registration_token(&self, token: &str) -> CommandResult<RegistrationToken> {
+    let path = format!("{TOKENS_PATH}/{}", urlencoding::encode(validate_token(token)?));
+    self.get_json(&path, "loading registration token").await
+  }
+
+  pub async fn create_registration_token(&self, request: &NewRegistrationToken) -> CommandResult<RegistrationToken> {
+    let mut request = request.clone();
+    if let Some(token) = request.token.as_deref() {
+      request.token = Some(validate_token(token)?.to_string());
+    } else if let Some(length) = request.length {
+      if length == 0 || length > MAX_TOKEN_LENGTH {
+        return Err(CommandError::invalid_input("Token length must be between 1 and 64."));
+      }
+    }
+    self
+      .send_json(
+        Method::POST,
+        &format!("{TOKENS_PATH}/new"),
+        &request,
+        "creating registration token",
+      )
+      .await
+  }
+
+  pub async fn update_registration_token(
+    &self,
+    token: &str,
+    update: &RegistrationTokenUpdate,
+  ) -> CommandResult<RegistrationToken> {
+    let path = format!("{TOKENS_PATH}/{}", urlencoding::encode(validate_token(token)?));
+    self
+      .send_json(Method::PUT, &path, update, "updating registration token")
+      .await
+  }
+
+  pub async fn delete_registration_token(&self, token: &str) -> CommandResult<()> {
+    let path = format!("{TOKENS_PATH}/{}", urlencoding::encode(validate_token(token)?));
+    self
+      .execute::<()>(Method::DELETE, &path, None, "deleting registration token")
+      .await
+  }
+}
+
+#[tauri::command]
+pub async fn synapse_list_registration_tokens(
+  app: AppHandle,
+  profile_id: String,
+  valid: Option<bool>,
+) -> CommandResult<Vec<RegistrationToken>> {
+  admin_client(&app, &profile_id).await?.list_registration_tokens(valid).await
+}
+
+#[tauri::command]
+pub async fn synapse_get_registration_token(
+  app: AppHandle,
+  profile_id: String,
+  token: String,
+) -> CommandResult<RegistrationToken> {
+  admin_client(&app, &profile_id).await?.registration_token(&token).await
+}
+
+#[tauri::command]
+pub async fn synapse_create_registration_token(
+  app: AppHandle,
+  profile_id: String,
+  request: Option<NewRegistrationToken>,
+) -> CommandResult<RegistrationToken> {
+  admin_client(&app, &profile_id)
+    .await?
+    .create_registration_token(&request.unwrap_or_default())
+    .await
+}
+
+#[tauri::command]
+pub async fn synapse_update_registration_token(
+  app: AppHandle,
+  profile_id: String,
+  token: String,
+  update: RegistrationTokenUpdate,
+) -> CommandResult<RegistrationToken> {
+  admin_client(&app, &profile_id).await?.update_registration_token(&token, &update).await
+}
+
+#[tauri::command]
+pub async fn synapse_delete_registration_token(
+  app: AppHandle,
+  profile_id: String,
+  token: String,
+) -> CommandResult<()> {
+  admin_client(&app, &profile_id).await?.delete_registration_token(&token).await
+}
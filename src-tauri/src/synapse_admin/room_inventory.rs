@@ -0,0 +1,200 @@
+use super::client::{with_query, SynapseAdminClient};
+use super::de::flexible_bool;
+use crate::error::{CommandError, CommandResult};
+use crate::server_profiles::admin_client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::AppHandle;
+
+pub(crate) fn require_room_id(room_id: &str) -> CommandResult<&str> {
+  let room_id = room_id.trim();
+  if !room_id.starts_with('!') {
+    return Err(CommandError::invalid_input(format!(
+      "\"{room_id}\" is not a valid Matrix room id."
+    )));
+  }
+  Ok(room_id)
+}
+
+fn room_path(room_id: &str, suffix: &str) -> CommandResult<String> {
+  let room_id = require_room_id(room_id)?;
+  Ok(format!("/_synapse/admin/v1/rooms/{}{suffix}", urlencoding::encode(room_id)))
+}
+
+/// Paging and sorting for `GET /_synapse/admin/v1/rooms`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RoomListQuery {
+  #[serde(default)]
+  pub from: Option<u64>,
+  #[serde(default)]
+  pub limit: Option<u32>,
+  /// e.g. `joined_members`, `state_events`, `name`, `size`.
+  #[serde(default)]
+  pub order_by: Option<String>,
+  /// `f` (forwards) or `b` (backwards).
+  #[serde(default)]
+  pub dir: Option<String>,
+  /// Matches room name, canonical alias or room id.
+  #[serde(default)]
+  pub search_term: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomRecord {
+  pub room_id: String,
+  #[serde(default)]
+  pub name: Option<String>,
+  #[serde(default)]
+  pub canonical_alias: Option<String>,
+  #[serde(default)]
+  pub joined_members: u64,
+  #[serde(default)]
+  pub joined_local_members: u64,
+  #[serde(default)]
+  pub state_events: u64,
+  #[serde(default)]
+  pub version: Option<String>,
+  #[serde(default)]
+  pub creator: Option<String>,
+  /// Encryption algorithm, e.g. `m.megolm.v1.aes-sha2`; `None` when unencrypted.
+  #[serde(default)]
+  pub encryption: Option<String>,
+  #[serde(default, deserialize_with = "flexible_bool")]
+  pub federatable: bool,
+  #[serde(default, deserialize_with = "flexible_bool")]
+  pub public: bool,
+  #[serde(default)]
+  pub join_rules: Option<String>,
+  #[serde(default)]
+  pub guest_access: Option<String>,
+  #[serde(default)]
+  pub history_visibility: Option<String>,
+  #[serde(default)]
+  pub room_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomListPage {
+  #[serde(default)]
+  pub rooms: Vec<RoomRecord>,
+  #[serde(default)]
+  pub offset: u64,
+  #[serde(default)]
+  pub total_rooms: u64,
+  /// Pass back as `from` for the next page; absent on the last page.
+  #[serde(default)]
+  pub next_batch: Option<u64>,
+  #[serde(default)]
+  pub prev_batch: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomDetails {
+  #[serde(flatten)]
+  pub room: RoomRecord,
+  #[serde(default)]
+  pub topic: Option<String>,
+  #[serde(default)]
+  pub avatar: Option<String>,
+  #[serde(default)]
+  pub joined_local_devices: u64,
+  #[serde(default, deserialize_with = "flexible_bool")]
+  pub forgotten: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomMembers {
+  #[serde(default)]
+  pub members: Vec<String>,
+  #[serde(default)]
+  pub total: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomStateEvent {
+  #[serde(rename = "type")]
+  pub event_type: String,
+  #[serde(default)]
+  pub state_key: String,
+  #[serde(default)]
+  pub sender: Option<String>,
+  #[serde(default)]
+  pub event_id: Option<String>,
+  #[serde(default)]
+  pub origin_server_ts: Option<u64>,
+  #[serde(default)]
+  pub content: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct RoomStateResponse {
+  #[serde(default)]
+  state: Vec<RoomStateEvent>,
+}
+
+impl SynapseAdminClient {
+  pub async fn list_rooms(&self, query: &RoomListQuery) -> CommandResult<RoomListPage> {
+    let path = with_query(
+      "/_synapse/admin/v1/rooms",
+      &[
+        ("from", query.from.map(|from| from.to_string())),
+        ("limit", query.limit.map(|limit| limit.to_string())),
+        ("order_by", query.order_by.clone()),
+        ("dir", query.dir.clone()),
+        ("search_term", query.search_term.clone()),
+      ],
+    );
+    self.get_json(&path, "listing rooms").await
+  }
+
+  pub async fn room_details(&self, room_id: &str) -> CommandResult<RoomDetails> {
+    self
+      .get_json(&room_path(room_id, "")?, "loading room details")
+      .await
+  }
+
+  pub async fn room_members(&self, room_id: &str) -> CommandResult<RoomMembers> {
+    self
+      .get_json(&room_path(room_id, "/members")?, "loading room members")
+      .await
+  }
+
+  pub async fn room_state(&self, room_id: &str) -> CommandResult<Vec<RoomStateEvent>> {
+    let response: RoomStateResponse = self
+      .get_json(&room_path(room_id, "/state")?, "loading room state")
+      .await?;
+    Ok(response.state)
+  }
+}
+
+#[tauri::command]
+pub async fn synapse_list_rooms(
+  app: AppHandle,
+  profile_id: String,
+  query: Option<RoomListQuery>,
+) -> CommandResult<RoomListPage> {
+  admin_client(&app, &profile_id).await?.list_rooms(&query.unwrap_or_default()).await
+}
+
+#[tauri::command]
+pub async fn synapse_get_room(app: AppHandle, profile_id: String, room_id: String) -> CommandResult<RoomDetails> {
+  admin_client(&app, &profile_id).await?.room_details(&room_id).await
+}
+
+#[tauri::command]
+pub async fn synapse_get_room_members(
+  app: AppHandle,
+  profile_id: String,
+  room_id: String,
+) -> CommandResult<RoomMembers> {
+  admin_client(&app, &profile_id).await?.room_members(&room_id).await
+}
+
+#[tauri::command]
+pub async fn synapse_get_room_state(
+  app: AppHandle,
+  profile_id: String,
+  room_id: String,
+) -> CommandResult<Vec<RoomStateEvent>> {
+  admin_client(&app, &profile_id).await?.room_state(&room_id).await
+}
@@ -0,0 +1,551 @@
+use super::client::{is_route_missing, SynapseAdminClient};
+use crate::error::{CommandError, CommandResult, ErrorKind};
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+
+const DELETE_WAIT_TIMEOUT: Duration = Duration::from_secs(90);
+const DELETE_POLL_INTERVAL: Duration = Duration::from_millis(1500);
+
+/// Body accepted by every room delete route (v2, v1 and the legacy POST).
+#[derive(Debug, Clone, Serialize)]
+pub struct DeleteRoomRequest {
+  pub block: bool,
+  pub purge: bool,
+  pub force_purge: bool,
+  pub requester_user_id: String,
+  /// Local user that creates a replacement room and moves members into it.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub new_room_user_id: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub room_name: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub message: Option<String>,
+}
+
+/// Delete modes exposed to the frontend. Omitted flags keep the original
+/// behaviour of blocking and force-purging the room.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RoomDeleteOptions {
+  #[serde(default)]
+  pub block: Option<bool>,
+  #[serde(default)]
+  pub purge: Option<bool>,
+  #[serde(default)]
+  pub force_purge: Option<bool>,
+  #[serde(default)]
+  pub new_room_user_id: Option<String>,
+  #[serde(default)]
+  pub room_name: Option<String>,
+  #[serde(default)]
+  pub message: Option<String>,
+}
+
+impl DeleteRoomRequest {
+  pub fn from_options(options: RoomDeleteOptions, requester_user_id: String) -> CommandResult<Self> {
+    let non_empty = |value: Option<String>| value.map(|text| text.trim().to_string()).filter(|text| !text.is_empty());
+    let purge = options.purge.unwrap_or(true);
+    let force_purge = options.force_purge.unwrap_or(purge);
+    if force_purge && !purge {
+      return Err(CommandError::invalid_input("force_purge requires purge to be enabled."));
+    }
+    let new_room_user_id = non_empty(options.new_room_user_id);
+    let room_name = non_empty(options.room_name);
+    let message = non_empty(options.message);
+    if new_room_user_id.is_none() && (room_name.is_some() || message.is_some()) {
+      return Err(CommandError::invalid_input(
+        "room_name and message only apply when new_room_user_id is set.",
+      ));
+    }
+    Ok(Self {
+      block: options.block.unwrap_or(true),
+      purge,
+      force_purge,
+      requester_user_id,
+      new_room_user_id,
+      room_name,
+      message,
+    })
+  }
+}
+
+/// Which delete route the homeserver accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeleteRoute {
+  V2,
+  V1,
+  Legacy,
+}
+
+/// Phases reported while a room delete runs, in the order they occur.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "phase", rename_all = "snake_case")]
+pub enum DeleteProgress {
+  Requested { route: DeleteRoute },
+  Accepted { route: DeleteRoute, delete_id: Option<String> },
+  /// Emitted whenever the polled `delete_status` value changes, e.g. `purging`.
+  Status { delete_id: String, status: String },
+  /// Waiting for `GET /rooms/{room_id}` to return 404.
+  Verifying,
+  Complete,
+  Failed { error: CommandError },
+}
+
+/// Hooks threaded through a delete so callers can observe progress and stop
+/// waiting. Cancelling never aborts the purge on the server, only our polling.
+#[derive(Clone, Copy)]
+pub struct DeleteObserver<'a> {
+  pub on_progress: &'a (dyn Fn(DeleteProgress) + Send + Sync),
+  pub cancelled: &'a AtomicBool,
+}
+
+impl DeleteObserver<'_> {
+  fn report(&self, progress: DeleteProgress) {
+    (self.on_progress)(progress);
+  }
+
+  fn ensure_active(&self) -> CommandResult<()> {
+    if self.cancelled.load(Ordering::SeqCst) {
+      return Err(CommandError::new(
+        ErrorKind::Cancelled,
+        "Stopped waiting for Synapse room purge.",
+      ));
+    }
+    Ok(())
+  }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct DeleteRoomResponse {
+  #[serde(default)]
+  pub delete_id: Option<String>,
+}
+
+/// Outcome of the shutdown half of a delete, reported by the v1 response body
+/// or the `shutdown_room` field of a v2 delete status.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ShutdownRoomResult {
+  #[serde(default)]
+  pub kicked_users: Vec<String>,
+  #[serde(default)]
+  pub failed_to_kick_users: Vec<String>,
+  #[serde(default)]
+  pub local_aliases: Vec<String>,
+  #[serde(default)]
+  pub new_room_id: Option<String>,
+  #[serde(default, skip_serializing)]
+  pub error: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct DeleteStatus {
+  #[serde(default)]
+  pub delete_id: Option<String>,
+  #[serde(default)]
+  pub status: Option<String>,
+  #[serde(default)]
+  pub error: Option<String>,
+  #[serde(default)]
+  pub shutdown_room: Option<ShutdownRoomResult>,
+}
+
+impl DeleteStatus {
+  pub fn error_message(&self) -> Option<String> {
+    let non_empty = |value: &Option<String>| value.clone().filter(|message| !message.trim().is_empty());
+    non_empty(&self.error).or_else(|| {
+      self
+        .shutdown_room
+        .as_ref()
+        .and_then(|shutdown| non_empty(&shutdown.error))
+    })
+  }
+}
+
+/// `delete_status/{delete_id}` returns a single status object while
+/// `{room_id}/delete_status` wraps every purge of the room in `results`.
+#[derive(Debug, Default, Deserialize)]
+struct DeleteStatusResponse {
+  #[serde(flatten)]
+  single: DeleteStatus,
+  #[serde(default)]
+  results: Vec<DeleteStatus>,
+}
+
+impl DeleteStatusResponse {
+  fn select(self, delete_id: &str) -> Option<DeleteStatus> {
+    if self.single.status.is_some() {
+      return Some(self.single);
+    }
+    let index = self
+      .results
+      .iter()
+      .position(|entry| entry.delete_id.as_deref() == Some(delete_id))
+      .unwrap_or(0);
+    self.results.into_iter().nth(index)
+  }
+}
+
+#[derive(Debug, Deserialize)]
+struct HierarchyRoom {
+  room_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct HierarchyResponse {
+  #[serde(default)]
+  rooms: Vec<HierarchyRoom>,
+  #[serde(default)]
+  next_batch: Option<String>,
+}
+
+impl SynapseAdminClient {
+  /// Lists every room below a space, including nested subspaces, using the
+  /// client-server hierarchy API. The space itself is not included.
+  pub async fn space_descendants(&self, space_id: &str) -> CommandResult<Vec<String>> {
+    let encoded_space_id = urlencoding::encode(space_id);
+    let mut room_ids = Vec::new();
+    let mut from: Option<String> = None;
+    loop {
+      let mut path = format!("/_matrix/client/v1/rooms/{encoded_space_id}/hierarchy?limit=50");
+      if let Some(token) = &from {
+        path.push_str(&format!("&from={}", urlencoding::encode(token)));
+      }
+      let page: HierarchyResponse = self.get_json(&path, "expanding space hierarchy").await?;
+      room_ids.extend(
+        page
+          .rooms
+          .into_iter()
+          .map(|room| room.room_id)
+          .filter(|room_id| room_id != space_id),
+      );
+      match page.next_batch {
+        Some(token) if !token.is_empty() => from = Some(token),
+        _ => return Ok(room_ids),
+      }
+    }
+  }
+
+  async fn poll_delete_status(
+    &self,
+    room_id: &str,
+    delete_id: &str,
+    observer: DeleteObserver<'_>,
+  ) -> CommandResult<Option<ShutdownRoomResult>> {
+    let started = Instant::now();
+    let mut use_delete_id_route = true;
+    let mut last_status: Option<String> = None;
+    let encoded_room_id = urlencoding::encode(room_id);
+    let encoded_delete_id = urlencoding::encode(delete_id);
+    while started.elapsed() < DELETE_WAIT_TIMEOUT {
+      observer.ensure_active()?;
+      let path = if use_delete_id_route {
+        format!("/_synapse/admin/v2/rooms/delete_status/{encoded_delete_id}")
+      } else {
+        format!("/_synapse/admin/v2/rooms/{encoded_room_id}/delete_status")
+      };
+      let response = self
+        .send_raw::<()>(Method::GET, &path, None, "polling delete status")
+        .await?;
+
+      if is_route_missing(response.status()) {
+        if use_delete_id_route {
+          use_delete_id_route = false;
+          continue;
+        }
+        // Older Synapse variants may not expose status routes; rely on room-existence verification.
+        return Ok(None);
+      }
+
+      let payload: DeleteStatusResponse = match Self::read_json(response, "polling delete status").await {
+        Ok(payload) => payload,
+        // A successful but unreadable status body just means no status yet.
+        Err(error) if error.kind == ErrorKind::Parse => DeleteStatusResponse::default(),
+        Err(error) => return Err(error),
+      };
+      if let Some(entry) = payload.select(delete_id) {
+        if let Some(status) = entry.status.as_deref() {
+          if last_status.as_deref() != Some(status) {
+            last_status = Some(status.to_string());
+            observer.report(DeleteProgress::Status {
+              delete_id: delete_id.to_string(),
+              status: status.to_string(),
+            });
+          }
+          if status.eq_ignore_ascii_case("complete") {
+            return Ok(entry.shutdown_room);
+          }
+          if status.eq_ignore_ascii_case("failed") {
+            return Err(CommandError::new(
+              ErrorKind::Synapse,
+              entry
+                .error_message()
+                .unwrap_or_else(|| "Synapse room deletion failed.".to_string()),
+            ));
+          }
+        }
+      }
+
+      sleep(DELETE_POLL_INTERVAL).await;
+    }
+
+    Err(CommandError::new(
+      ErrorKind::Timeout,
+      "Timed out waiting for Synapse room purge completion.",
+    ))
+  }
+
+  async fn wait_for_room_removal(&self, room_id: &str, observer: DeleteObserver<'_>) -> CommandResult<()> {
+    observer.report(DeleteProgress::Verifying);
+    let started = Instant::now();
+    let path = format!("/_synapse/admin/v1/rooms/{}", urlencoding::encode(room_id));
+    while started.elapsed() < DELETE_WAIT_TIMEOUT {
+      observer.ensure_active()?;
+      let response = self
+        .send_raw::<()>(Method::GET, &path, None, "verifying room purge")
+        .await?;
+
+      if response.status() == StatusCode::NOT_FOUND {
+        return Ok(());
+      }
+      if !response.status().is_success() {
+        return Err(CommandError::from_response(response).await);
+      }
+
+      sleep(DELETE_POLL_INTERVAL).await;
+    }
+
+    Err(CommandError::new(
+      ErrorKind::Timeout,
+      "Synapse still reports this room after deletion. Purge did not complete.",
+    ))
+  }
+
+  /// Purged rooms disappear from the admin rooms API; without `purge` the room
+  /// stays in the database, so there is nothing to verify.
+  async fn finish_delete(
+    &self,
+    room_id: &str,
+    verify_removal: bool,
+    result: ShutdownRoomResult,
+    observer: DeleteObserver<'_>,
+  ) -> CommandResult<ShutdownRoomResult> {
+    if verify_removal {
+      self.wait_for_room_removal(room_id, observer).await?;
+    }
+    observer.report(DeleteProgress::Complete);
+    Ok(result)
+  }
+
+  /// Deletes a room, trying the async v2 API first and falling back to the
+  /// synchronous v1 and legacy routes on older homeservers.
+  ///
+  /// The observer receives every phase, including a final `Failed` when the
+  /// delete returns an error.
+  pub async fn hard_delete_room(
+    &self,
+    room_id: &str,
+    request: &DeleteRoomRequest,
+    observer: DeleteObserver<'_>,
+  ) -> CommandResult<ShutdownRoomResult> {
+    let result = self.run_hard_delete(room_id, request, observer).await;
+    if let Err(error) = &result {
+      observer.report(DeleteProgress::Failed { error: error.clone() });
+    }
+    result
+  }
+
+  /// Re-attaches to a v2 delete that Synapse is already running, e.g. after the
+  /// app restarted while a purge was in flight.
+  pub async fn watch_delete(
+    &self,
+    room_id: &str,
+    delete_id: &str,
+    verify_removal: bool,
+    observer: DeleteObserver<'_>,
+  ) -> CommandResult<ShutdownRoomResult> {
+    let result = match self.poll_delete_status(room_id, delete_id, observer).await {
+      Ok(shutdown) => {
+        self
+          .finish_delete(room_id, verify_removal, shutdown.unwrap_or_default(), observer)
+          .await
+      }
+      Err(error) => Err(error),
+    };
+    if let Err(error) = &result {
+      observer.report(DeleteProgress::Failed { error: error.clone() });
+    }
+    result
+  }
+
+  async fn run_hard_delete(
+    &self,
+    room_id: &str,
+    request: &DeleteRoomRequest,
+    observer: DeleteObserver<'_>,
+  ) -> CommandResult<ShutdownRoomResult> {
+    let encoded_room_id = urlencoding::encode(room_id);
+
+    observer.report(DeleteProgress::Requested { route: DeleteRoute::V2 });
+    let v2_response = self
+      .send_raw(
+        Method::DELETE,
+        &format!("/_synapse/admin/v2/rooms/{encoded_room_id}"),
+        Some(request),
+        "deleting room",
+      )
+      .await?;
+    if !is_route_missing(v2_response.status()) {
+      let payload: DeleteRoomResponse = Self::read_json(v2_response, "deleting room").await?;
+      observer.report(DeleteProgress::Accepted {
+        route: DeleteRoute::V2,
+        delete_id: payload.delete_id.clone(),
+      });
+      let shutdown = match payload.delete_id.as_deref() {
+        Some(delete_id) => self.poll_delete_status(room_id, delete_id, observer).await?,
+        None => None,
+      };
+      return self
+        .finish_delete(room_id, request.purge, shutdown.unwrap_or_default(), observer)
+        .await;
+    }
+
+    observer.report(DeleteProgress::Requested { route: DeleteRoute::V1 });
+    let v1_response = self
+      .send_raw(
+        Method::DELETE,
+        &format!("/_synapse/admin/v1/rooms/{encoded_room_id}"),
+        Some(request),
+        "deleting room (v1)",
+      )
+      .await?;
+    if !is_route_missing(v1_response.status()) {
+      let shutdown: ShutdownRoomResult = Self::read_json(v1_response, "deleting room (v1)").await?;
+      observer.report(DeleteProgress::Accepted {
+        route: DeleteRoute::V1,
+        delete_id: None,
+      });
+      return self.finish_delete(room_id, request.purge, shutdown, observer).await;
+    }
+
+    observer.report(DeleteProgress::Requested { route: DeleteRoute::Legacy });
+    let legacy_response = self
+      .send_raw(
+        Method::POST,
+        &format!("/_synapse/admin/v1/rooms/{encoded_room_id}/delete"),
+        Some(request),
+        "deleting room (legacy)",
+      )
+      .await?;
+    let shutdown: ShutdownRoomResult = Self::read_json(legacy_response, "deleting room (legacy)").await?;
+    observer.report(DeleteProgress::Accepted {
+      route: DeleteRoute::Legacy,
+      delete_id: None,
+    });
+
+    self.finish_delete(room_id, request.purge, shutdown, observer).await
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  fn status_response(body: serde_json::Value) -> DeleteStatusResponse {
+    serde_json::from_value(body).expect("delete status fixture")
+  }
+
+  #[test]
+  fn select_prefers_single_status_object() {
+    let response = status_response(json!({ "status": "purging" }));
+    let entry = response.select("abc").expect("single status");
+    assert_eq!(entry.status.as_deref(), Some("purging"));
+  }
+
+  #[test]
+  fn select_matches_delete_id_in_results() {
+    let response = status_response(json!({
+      "results": [
+        { "delete_id": "old", "status": "complete" },
+        { "delete_id": "abc", "status": "failed", "error": "boom" },
+      ]
+    }));
+    let entry = response.select("abc").expect("matching entry");
+    assert_eq!(entry.status.as_deref(), Some("failed"));
+    assert_eq!(entry.error_message().as_deref(), Some("boom"));
+  }
+
+  #[test]
+  fn select_falls_back_to_first_result() {
+    let response = status_response(json!({
+      "results": [{ "delete_id": "other", "status": "shutting_down" }]
+    }));
+    let entry = response.select("abc").expect("first entry");
+    assert_eq!(entry.delete_id.as_deref(), Some("other"));
+  }
+
+  #[test]
+  fn select_returns_none_without_status() {
+    assert!(status_response(json!({})).select("abc").is_none());
+  }
+
+  #[test]
+  fn from_options_defaults_to_block_and_force_purge() {
+    let request = DeleteRoomRequest::from_options(RoomDeleteOptions::default(), "@admin:example.org".into())
+      .expect("default options");
+    assert!(request.block);
+    assert!(request.purge);
+    assert!(request.force_purge);
+    assert!(request.new_room_user_id.is_none());
+  }
+
+  #[test]
+  fn from_options_force_purge_follows_purge() {
+    let options = RoomDeleteOptions {
+      purge: Some(false),
+      ..Default::default()
+    };
+    let request = DeleteRoomRequest::from_options(options, "@admin:example.org".into()).expect("no purge");
+    assert!(!request.purge);
+    assert!(!request.force_purge);
+  }
+
+  #[test]
+  fn from_options_rejects_force_purge_without_purge() {
+    let options = RoomDeleteOptions {
+      purge: Some(false),
+      force_purge: Some(true),
+      ..Default::default()
+    };
+    let error = DeleteRoomRequest::from_options(options, "@admin:example.org".into()).unwrap_err();
+    assert_eq!(error.kind, ErrorKind::InvalidInput);
+  }
+
+  #[test]
+  fn from_options_rejects_replacement_fields_without_new_room_user() {
+    let options = RoomDeleteOptions {
+      new_room_user_id: Some("  ".into()),
+      message: Some("Moved".into()),
+      ..Default::default()
+    };
+    let error = DeleteRoomRequest::from_options(options, "@admin:example.org".into()).unwrap_err();
+    assert_eq!(error.kind, ErrorKind::InvalidInput);
+  }
+
+  #[test]
+  fn from_options_trims_replacement_fields() {
+    let options = RoomDeleteOptions {
+      new_room_user_id: Some(" @bot:example.org ".into()),
+      room_name: Some(" Moved ".into()),
+      message: Some("".into()),
+      ..Default::default()
+    };
+    let request = DeleteRoomRequest::from_options(options, "@admin:example.org".into()).expect("replacement");
+    assert_eq!(request.new_room_user_id.as_deref(), Some("@bot:example.org"));
+    assert_eq!(request.room_name.as_deref(), Some("Moved"));
+    assert!(request.message.is_none());
+  }
+}
@@ -0,0 +1,121 @@
+use super::client::SynapseAdminClient;
+use super::users::{require_user_id, UserListQuery};
+use crate::error::{CommandError, CommandResult};
+use crate::server_profiles::admin_client;
+use reqwest::Method;
+use serde::Serialize;
+use serde_json::json;
+use tauri::{AppHandle, Emitter};
+
+const SERVER_NOTICE_PROGRESS_EVENT: &str = "server-notice-progress";
+const USER_PAGE_SIZE: u32 = 100;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerNoticeFailure {
+  pub user_id: String,
+  pub error: CommandError,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerNoticeSummary {
+  pub dry_run: bool,
+  pub recipients: usize,
+  pub sent: usize,
+  pub failed: Vec<ServerNoticeFailure>,
+}
+
+#[derive(Clone, Serialize)]
+struct ServerNoticeProgressEvent<'a> {
+  user_id: &'a str,
+  ok: bool,
+  completed: usize,
+  total: usize,
+}
+
+impl SynapseAdminClient {
+  pub async fn send_server_notice(&self, user_id: &str, body: &str) -> CommandResult<()> {
+    let request = json!({
+      "user_id": require_user_id(user_id)?,
+      "content": { "msgtype": "m.text", "body": body },
+    });
+    self
+      .execute(
+        Method::POST,
+        "/_synapse/admin/v1/send_server_notice",
+        Some(&request),
+        "sending server notice",
+      )
+      .await
+  }
+
+  /// Every active, non-guest account on the homeserver.
+  pub async fn local_user_ids(&self) -> CommandResult<Vec<String>> {
+    let mut query = UserListQuery {
+      limit: Some(USER_PAGE_SIZE),
+      guests: Some(false),
+      deactivated: Some(false),
+      ..UserListQuery::default()
+    };
+    let mut user_ids = Vec::new();
+    loop {
+      let page = self.list_users(&query).await?;
+      user_ids.extend(page.users.into_iter().map(|user| user.name));
+      match page.next_token {
+        Some(token) if !token.is_empty() => query.from = Some(token),
+        _ => return Ok(user_ids),
+      }
+    }
+  }
+}
+
+/// Sends `message` as a server notice to `user_id`, or to every local user when
+/// no user is given. With `dry_run` only the recipient count is returned.
+/// Each delivery emits a `server-notice-progress` event.
+#[tauri::command]
+pub async fn synapse_send_server_notice(
+  app: AppHandle,
+  profile_id: String,
+  message: String,
+  user_id: Option<String>,
+  dry_run: Option<bool>,
+) -> CommandResult<ServerNoticeSummary> {
+  if message.trim().is_empty() {
+    return Err(CommandError::invalid_input("A notice message is required."));
+  }
+  let client = admin_client(&app, &profile_id).await?;
+  let recipients = match user_id.as_deref().map(str::trim).filter(|id| !id.is_empty()) {
+    Some(user_id) => vec![require_user_id(user_id)?.to_string()],
+    None => client.local_user_ids().await?,
+  };
+
+  let mut summary = ServerNoticeSummary {
+    dry_run: dry_run.unwrap_or(false),
+    recipients: recipients.len(),
+    sent: 0,
+    failed: Vec::new(),
+  };
+  if summary.dry_run {
+    return Ok(summary);
+  }
+
+  for (index, recipient) in recipients.iter().enumerate() {
+    let result = client.send_server_notice(recipient, &message).await;
+    let event = ServerNoticeProgressEvent {
+      user_id: recipient,
+      ok: result.is_ok(),
+      completed: index + 1,
+      total: summary.recipients,
+    };
+    if let Err(error) = app.emit(SERVER_NOTICE_PROGRESS_EVENT, event) {
+      log::warn!("Failed to emit server notice progress: {error}");
+    }
+    match result {
+      Ok(()) => summary.sent += 1,
+      Err(error) => summary.failed.push(ServerNoticeFailure {
+        user_id: recipient.clone(),
+        error,
+      }),
+    }
+  }
+  Ok(summary)
+}
@@ -0,0 +1,330 @@
+use super::client::{with_query, SynapseAdminClient};
+use super::de::flexible_bool;
+use crate::error::{CommandError, CommandResult};
+use crate::server_profiles::admin_client;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::AppHandle;
+
+pub(crate) fn require_user_id(user_id: &str) -> CommandResult<&str> {
+  let user_id = user_id.trim();
+  if !user_id.starts_with('@') || !user_id.contains(':') {
+    return Err(CommandError::invalid_input(format!(
+      "\"{user_id}\" is not a valid Matrix user id."
+    )));
+  }
+  Ok(user_id)
+}
+
+fn user_path(prefix: &str, user_id: &str, suffix: &str) -> CommandResult<String> {
+  let user_id = require_user_id(user_id)?;
+  Ok(format!("{prefix}/{}{suffix}", urlencoding::encode(user_id)))
+}
+
+/// Filters for `GET /_synapse/admin/v2/users`. Unset fields use Synapse defaults.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UserListQuery {
+  #[serde(default)]
+  pub from: Option<String>,
+  #[serde(default)]
+  pub limit: Option<u32>,
+  /// Substring match on user id or display name.
+  #[serde(default)]
+  pub name: Option<String>,
+  #[serde(default)]
+  pub guests: Option<bool>,
+  #[serde(default)]
+  pub deactivated: Option<bool>,
+  #[serde(default)]
+  pub admins: Option<bool>,
+  #[serde(default)]
+  pub order_by: Option<String>,
+  /// `f` (forwards) or `b` (backwards).
+  #[serde(default)]
+  pub dir: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserSummary {
+  pub name: String,
+  #[serde(default)]
+  pub displayname: Option<String>,
+  #[serde(default)]
+  pub avatar_url: Option<String>,
+  #[serde(default)]
+  pub user_type: Option<String>,
+  #[serde(default, deserialize_with = "flexible_bool")]
+  pub is_guest: bool,
+  #[serde(default, deserialize_with = "flexible_bool")]
+  pub admin: bool,
+  #[serde(default, deserialize_with = "flexible_bool")]
+  pub deactivated: bool,
+  #[serde(default, deserialize_with = "flexible_bool")]
+  pub shadow_banned: bool,
+  #[serde(default, deserialize_with = "flexible_bool")]
+  pub locked: bool,
+  #[serde(default)]
+  pub creation_ts: Option<u64>,
+  #[serde(default)]
+  pub last_seen_ts: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserListPage {
+  #[serde(default)]
+  pub users: Vec<UserSummary>,
+  /// Pass back as `from` to fetch the next page; absent on the last page.
+  #[serde(default)]
+  pub next_token: Option<String>,
+  #[serde(default)]
+  pub total: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserThreepid {
+  pub medium: String,
+  pub address: String,
+  #[serde(default)]
+  pub added_at: Option<u64>,
+  #[serde(default)]
+  pub validated_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserExternalId {
+  pub auth_provider: String,
+  pub external_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserDetails {
+  pub name: String,
+  #[serde(default)]
+  pub displayname: Option<String>,
+  #[serde(default)]
+  pub avatar_url: Option<String>,
+  #[serde(default)]
+  pub user_type: Option<String>,
+  #[serde(default, deserialize_with = "flexible_bool")]
+  pub is_guest: bool,
+  #[serde(default, deserialize_with = "flexible_bool")]
+  pub admin: bool,
+  #[serde(default, deserialize_with = "flexible_bool")]
+  pub deactivated: bool,
+  #[serde(default, deserialize_with = "flexible_bool")]
+  pub erased: bool,
+  #[serde(default, deserialize_with = "flexible_bool")]
+  pub shadow_banned: bool,
+  #[serde(default, deserialize_with = "flexible_bool")]
+  pub locked: bool,
+  #[serde(default)]
+  pub creation_ts: Option<u64>,
+  #[serde(default)]
+  pub last_seen_ts: Option<u64>,
+  #[serde(default)]
+  pub appservice_id: Option<String>,
+  #[serde(default)]
+  pub threepids: Vec<UserThreepid>,
+  #[serde(default)]
+  pub external_ids: Vec<UserExternalId>,
+}
+
+/// Per-user override of the homeserver message rate limit. Zero for both
+/// fields disables rate limiting for the user entirely. Unset fields are
+/// left out of the request, since Synapse rejects `null` but treats a
+/// missing field as zero.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RateLimitOverride {
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub messages_per_second: Option<u64>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub burst_count: Option<u64>,
+}
+
+fn bool_param(value: Option<bool>) -> Option<String> {
+  value.map(|value| value.to_string())
+}
+
+impl SynapseAdminClient {
+  pub async fn list_users(&self, query: &UserListQuery) -> CommandResult<UserListPage> {
+    let path = with_query(
+      "/_synapse/admin/v2/users",
+      &[
+        ("from", query.from.clone()),
+        ("limit", query.limit.map(|limit| limit.to_string())),
+        ("name", query.name.clone()),
+        ("guests", bool_param(query.guests)),
+        ("deactivated", bool_param(query.deactivated)),
+        ("admins", bool_param(query.admins)),
+        ("order_by", query.order_by.clone()),
+        ("dir", query.dir.clone()),
+      ],
+    );
+    self.get_json(&path, "listing users").await
+  }
+
+  pub async fn user_details(&self, user_id: &str) -> CommandResult<UserDetails> {
+    let path = user_path("/_synapse/admin/v2/users", user_id, "")?;
+    self.get_json(&path, "loading user details").await
+  }
+
+  pub async fn deactivate_user(&self, user_id: &str, erase: bool) -> CommandResult<()> {
+    let path = user_path("/_synapse/admin/v1/deactivate", user_id, "")?;
+    self
+      .execute(Method::POST, &path, Some(&json!({ "erase": erase })), "deactivating user")
+      .await
+  }
+
+  pub async fn reset_password(&self, user_id: &str, new_password: &str, logout_devices: bool) -> CommandResult<()> {
+    if new_password.is_empty() {
+      return Err(CommandError::invalid_input("A new password is required."));
+    }
+    let path = user_path("/_synapse/admin/v1/reset_password", user_id, "")?;
+    let body = json!({ "new_password": new_password, "logout_devices": logout_devices });
+    self
+      .execute(Method::POST, &path, Some(&body), "resetting password")
+      .await
+  }
+
+  pub async fn set_admin(&self, user_id: &str, admin: bool) -> CommandResult<()> {
+    let path = user_path("/_synapse/admin/v1/users", user_id, "/admin")?;
+    self
+      .execute(Method::PUT, &path, Some(&json!({ "admin": admin })), "updating admin status")
+      .await
+  }
+
+  pub async fn set_shadow_ban(&self, user_id: &str, shadow_banned: bool) -> CommandResult<()> {
+    let path = user_path("/_synapse/admin/v1/users", user_id, "/shadow_ban")?;
+    let method = if shadow_banned { Method::POST } else { Method::DELETE };
+    self
+      .execute::<()>(method, &path, None, "updating shadow ban")
+      .await
+  }
+
+  pub async fn ratelimit_override(&self, user_id: &str) -> CommandResult<Option<RateLimitOverride>> {
+    let path = user_path("/_synapse/admin/v1/users", user_id, "/override_ratelimit")?;
+    let current: RateLimitOverride = self.get_json(&path, "reading rate limit override").await?;
+    // Synapse returns `{}` when no override is set.
+    if current.messages_per_second.is_none() && current.burst_count.is_none() {
+      return Ok(None);
+    }
+    Ok(Some(current))
+  }
+
+  pub async fn set_ratelimit_override(
+    &self,
+    user_id: &str,
+    ratelimit: Option<&RateLimitOverride>,
+  ) -> CommandResult<()> {
+    let path = user_path("/_synapse/admin/v1/users", user_id, "/override_ratelimit")?;
+    match ratelimit {
+      Some(ratelimit) => {
+        self
+          .execute(Method::POST, &path, Some(ratelimit), "setting rate limit override")
+          .await
+      }
+      None => {
+        self
+          .execute::<()>(Method::DELETE, &path, None, "clearing rate limit override")
+          .await
+      }
+    }
+  }
+}
+
+#[tauri::command]
+pub async fn synapse_list_users(
+  app: AppHandle,
+  profile_id: String,
+  query: Option<UserListQuery>,
+) -> CommandResult<UserListPage> {
+  admin_client(&app, &profile_id).await?.list_users(&query.unwrap_or_default()).await
+}
+
+#[tauri::command]
+pub async fn synapse_get_user(app: AppHandle, profile_id: String, user_id: String) -> CommandResult<UserDetails> {
+  admin_client(&app, &profile_id).await?.user_details(&user_id).await
+}
+
+/// Deactivates an account. With `erase` Synapse also forgets the user's
+/// messages for future joiners (GDPR erasure).
+#[tauri::command]
+pub async fn synapse_deactivate_user(
+  app: AppHandle,
+  profile_id: String,
+  user_id: String,
+  erase: Option<bool>,
+) -> CommandResult<()> {
+  admin_client(&app, &profile_id).await?.deactivate_user(&user_id, erase.unwrap_or(false)).await
+}
+
+#[tauri::command]
+pub async fn synapse_reset_user_password(
+  app: AppHandle,
+  profile_id: String,
+  user_id: String,
+  new_password: String,
+  logout_devices: Option<bool>,
+) -> CommandResult<()> {
+  admin_client(&app, &profile_id)
+    .await?
+    .reset_password(&user_id, &new_password, logout_devices.unwrap_or(true))
+    .await
+}
+
+#[tauri::command]
+pub async fn synapse_set_user_admin(
+  app: AppHandle,
+  profile_id: String,
+  user_id: String,
+  admin: bool,
+) -> CommandResult<()> {
+  admin_client(&app, &profile_id).await?.set_admin(&user_id, admin).await
+}
+
+#[tauri::command]
+pub async fn synapse_set_user_shadow_ban(
+  app: AppHandle,
+  profile_id: String,
+  user_id: String,
+  shadow_banned: bool,
+) -> CommandResult<()> {
+  admin_client(&app, &profile_id).await?.set_shadow_ban(&user_id, shadow_banned).await
+}
+
+#[tauri::command]
+pub async fn synapse_get_user_ratelimit(
+  app: AppHandle,
+  profile_id: String,
+  user_id: String,
+) -> CommandResult<Option<RateLimitOverride>> {
+  admin_client(&app, &profile_id).await?.ratelimit_override(&user_id).await
+}
+
+/// Sets a rate limit override, or removes it when `ratelimit` is null.
+#[tauri::command]
+pub async fn synapse_set_user_ratelimit(
+  app: AppHandle,
+  profile_id: String,
+  user_id: String,
+  ratelimit: Option<RateLimitOverride>,
+) -> CommandResult<()> {
+  admin_client(&app, &profile_id).await?.set_ratelimit_override(&user_id, ratelimit.as_ref()).await
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  #[test]
+  fn partial_ratelimit_override_omits_unset_fields() {
+    let ratelimit = RateLimitOverride {
+      messages_per_second: Some(5),
+      burst_count: None,
+    };
+    assert_eq!(serde_json::to_value(&ratelimit).unwrap(), json!({ "messages_per_second": 5 }));
+    assert_eq!(serde_json::to_value(RateLimitOverride::default()).unwrap(), json!({}));
+  }
+}
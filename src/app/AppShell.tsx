@@ -738,6 +738,7 @@ const AppShell = () => {
             rooms={spaceRooms}
             categories={currentCategories}
             matrixBaseUrl={matrixSession?.baseUrl ?? null}
+            matrixAccessToken={matrixSession?.accessToken ?? null}
             canViewInfrastructureHealth={canViewInfrastructureHealth}
             settings={currentServerSettings}
             permissionOverrides={currentPermissionOverrides}
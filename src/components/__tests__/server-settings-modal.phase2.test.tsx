@@ -2,11 +2,17 @@ import { render, screen, waitFor } from "@testing-library/react";
 import userEvent from "@testing-library/user-event";
 import { describe, expect, it, vi } from "vitest";
 import { Category, Room, ServerSettings, Space, User } from "../../types";
-import { fetchServerHealthSnapshot } from "../../services/serverHealthService";
+import {
+  ServerProfile,
+  fetchServerHealthSnapshot,
+  saveServerProfile
+} from "../../services/serverHealthService";
 import { ServerSettingsModal } from "../ServerSettingsModal";
 
 vi.mock("../../services/serverHealthService", () => ({
-  fetchServerHealthSnapshot: vi.fn()
+  fetchServerHealthSnapshot: vi.fn(),
+  listServerProfiles: vi.fn().mockResolvedValue([]),
+  saveServerProfile: vi.fn()
 }));
 
 const space: Space = { id: "s_fray", name: "Fray HQ", icon: "F" };
@@ -53,6 +59,7 @@ const settings: ServerSettings = {
 
 describe("Phase 2 server settings modal", () => {
   const mockedFetchServerHealthSnapshot = vi.mocked(fetchServerHealthSnapshot);
+  const mockedSaveServerProfile = vi.mocked(saveServerProfile);
 
   it("saves invite settings from the Invites tab", async () => {
     const user = userEvent.setup();
@@ -332,6 +339,8 @@ describe("Phase 2 server settings modal", () => {
   it("loads infrastructure health metrics in the Health tab", async () => {
     const user = userEvent.setup();
     mockedFetchServerHealthSnapshot.mockReset();
+    mockedSaveServerProfile.mockReset();
+    mockedSaveServerProfile.mockResolvedValue({} as ServerProfile);
     mockedFetchServerHealthSnapshot.mockResolvedValue({
       captured_at: 1700000000000,
       host: {
@@ -385,6 +394,7 @@ describe("Phase 2 server settings modal", () => {
         space={space}
         rooms={rooms}
         categories={categories}
+        matrixAccessToken="syt_admin_token"
         settings={settings}
         permissionOverrides={{ version: 1, categories: {}, rooms: {} }}
         moderationAudit={[]}
@@ -415,17 +425,30 @@ describe("Phase 2 server settings modal", () => {
     await user.type(screen.getByLabelText("SSH User"), "root");
     await user.type(screen.getByLabelText("SSH Password (Optional)"), "test-pass-123");
     await user.click(screen.getByLabelText("Auto-refresh every 10 seconds"));
-    await user.click(screen.getByRole("button", { name: "Refresh Health" }));
+    expect(screen.getByRole("button", { name: "Refresh Health" })).toBeDisabled();
+    await user.click(screen.getByRole("button", { name: "Save Connection" }));
 
     await waitFor(() => {
-      expect(mockedFetchServerHealthSnapshot).toHaveBeenCalledWith(
-        expect.objectContaining({
-          host: "matrix.example.com",
-          username: "root",
-          password: "test-pass-123"
-        })
-      );
+      expect(mockedFetchServerHealthSnapshot).toHaveBeenCalledWith("s_fray");
+    });
+    expect(mockedSaveServerProfile).toHaveBeenCalledTimes(1);
+    expect(mockedSaveServerProfile).toHaveBeenCalledWith(
+      expect.objectContaining({
+        profileId: "s_fray",
+        host: "matrix.example.com",
+        username: "root",
+        password: "test-pass-123",
+        accessToken: "syt_admin_token"
+      })
+    );
+    expect(screen.getByLabelText("SSH Password (Optional)")).toHaveValue("");
+
+    await user.click(screen.getByRole("button", { name: "Refresh Health" }));
+    await waitFor(() => {
+      expect(mockedFetchServerHealthSnapshot).toHaveBeenCalledTimes(2);
     });
+    expect(mockedFetchServerHealthSnapshot).toHaveBeenLastCalledWith("s_fray");
+    expect(mockedSaveServerProfile).toHaveBeenCalledTimes(1);
 
     expect(screen.getByText("Infrastructure Health")).toBeInTheDocument();
     expect(screen.getByText("1.147.0")).toBeInTheDocument();
@@ -507,6 +530,8 @@ describe("Phase 2 server settings modal", () => {
   it("does not refresh health while editing host fields", async () => {
     const user = userEvent.setup();
     mockedFetchServerHealthSnapshot.mockReset();
+    mockedSaveServerProfile.mockReset();
+    mockedSaveServerProfile.mockResolvedValue({} as ServerProfile);
     window.localStorage.removeItem("fray.server.health.prefs");
     mockedFetchServerHealthSnapshot.mockResolvedValue({
       captured_at: 1700000000000,
@@ -573,8 +598,9 @@ describe("Phase 2 server settings modal", () => {
     await user.type(screen.getByLabelText("SSH User"), "root");
 
     expect(mockedFetchServerHealthSnapshot).not.toHaveBeenCalled();
+    expect(mockedSaveServerProfile).not.toHaveBeenCalled();
 
-    await user.click(screen.getByRole("button", { name: "Refresh Health" }));
+    await user.click(screen.getByRole("button", { name: "Save Connection" }));
 
     await waitFor(() => {
       expect(mockedFetchServerHealthSnapshot).toHaveBeenCalledTimes(1);
@@ -16,7 +16,9 @@ import {
 import { ServerSettingsTab } from "../../store/appStore";
 import {
   ServerHealthSnapshot,
-  fetchServerHealthSnapshot
+  fetchServerHealthSnapshot,
+  listServerProfiles,
+  saveServerProfile
 } from "../../services/serverHealthService";
 
 interface ServerSettingsModalProps {
@@ -24,6 +26,7 @@ interface ServerSettingsModalProps {
   rooms: Room[];
   categories: Category[];
   matrixBaseUrl?: string | null;
+  matrixAccessToken?: string | null;
   canViewInfrastructureHealth?: boolean;
   settings?: ServerSettings;
   permissionOverrides?: SpacePermissionOverrides;
@@ -144,7 +147,6 @@ interface HealthPreferences {
   host: string;
   useMatrixHost: boolean;
   username: string;
-  password: string;
   synapseContainer: string;
   postgresContainer: string;
   postgresUser: string;
@@ -167,7 +169,6 @@ const getDefaultHealthPreferences = (matrixBaseUrl?: string | null): HealthPrefe
     host,
     useMatrixHost: true,
     username: "root",
-    password: "",
     synapseContainer: "fray-synapse",
     postgresContainer: "fray-postgres",
     postgresUser: "synapse",
@@ -198,7 +199,6 @@ const loadHealthPreferences = (
       host: storedHost,
       useMatrixHost: storedUseMatrixHost,
       username: typeof perSpace.username === "string" ? perSpace.username : defaults.username,
-      password: typeof perSpace.password === "string" ? perSpace.password : defaults.password,
       synapseContainer:
         typeof perSpace.synapseContainer === "string"
           ? perSpace.synapseContainer
@@ -267,6 +267,7 @@ export const ServerSettingsModal = ({
   rooms,
   categories,
   matrixBaseUrl,
+  matrixAccessToken,
   canViewInfrastructureHealth = true,
   settings,
   permissionOverrides,
@@ -345,19 +346,12 @@ export const ServerSettingsModal = ({
   const [healthAutoRefresh, setHealthAutoRefresh] = useState(true);
   const [healthSnapshot, setHealthSnapshot] = useState<ServerHealthSnapshot | null>(null);
   const [healthLoading, setHealthLoading] = useState(false);
+  const [healthSaving, setHealthSaving] = useState(false);
   const [healthError, setHealthError] = useState<string | null>(null);
+  const [healthProfileSaved, setHealthProfileSaved] = useState(false);
   const resolvedHealthHost =
     healthUseMatrixHost && matrixHost.trim() ? matrixHost.trim() : healthHost.trim();
   const healthRequestInFlightRef = useRef(false);
-  const healthConfigRef = useRef({
-    host: "",
-    username: "root",
-    password: "",
-    synapseContainer: "fray-synapse",
-    postgresContainer: "fray-postgres",
-    postgresUser: "synapse",
-    postgresDatabase: "synapse"
-  });
   const [healthConfigRevision, setHealthConfigRevision] = useState(0);
   const availableTabs = useMemo(
     () => tabs.filter((tab) => tab.id !== "health" || canViewInfrastructureHealth),
@@ -423,7 +417,8 @@ export const ServerSettingsModal = ({
     setHealthHost(preferences.host);
     setHealthUseMatrixHost(useMatrixHost);
     setHealthUsername(preferences.username);
-    setHealthPassword(preferences.password);
+    // The SSH password lives in the OS keychain once saved; never prefill it.
+    setHealthPassword("");
     setHealthSynapseContainer(preferences.synapseContainer);
     setHealthPostgresContainer(preferences.postgresContainer);
     setHealthPostgresUser(preferences.postgresUser);
@@ -441,7 +436,6 @@ export const ServerSettingsModal = ({
       host: healthHost,
       useMatrixHost: healthUseMatrixHost,
       username: healthUsername,
-      password: healthPassword,
       synapseContainer: healthSynapseContainer,
       postgresContainer: healthPostgresContainer,
       postgresUser: healthPostgresUser,
@@ -452,7 +446,6 @@ export const ServerSettingsModal = ({
     healthAutoRefresh,
     healthHost,
     healthUseMatrixHost,
-    healthPassword,
     healthPostgresContainer,
     healthPostgresDatabase,
     healthPostgresUser,
@@ -462,31 +455,67 @@ export const ServerSettingsModal = ({
   ]);
 
   useEffect(() => {
-    healthConfigRef.current = {
-      host: resolvedHealthHost,
-      username: healthUsername,
-      password: healthPassword,
-      synapseContainer: healthSynapseContainer,
-      postgresContainer: healthPostgresContainer,
-      postgresUser: healthPostgresUser,
-      postgresDatabase: healthPostgresDatabase
+    let cancelled = false;
+    setHealthProfileSaved(false);
+    void listServerProfiles()
+      .then((profiles) => {
+        if (!cancelled) {
+          setHealthProfileSaved(profiles.some((profile) => profile.id === space.id));
+        }
+      })
+      .catch(() => undefined);
+    return () => {
+      cancelled = true;
     };
+  }, [space.id]);
+
+  const saveHealthConnection = useCallback(async () => {
+    if (!resolvedHealthHost || !healthUsername.trim()) {
+      setHealthError("Host and SSH username are required to load server health.");
+      return;
+    }
+    setHealthSaving(true);
+    setHealthError(null);
+    try {
+      await saveServerProfile({
+        profileId: space.id,
+        profileName: space.name,
+        host: resolvedHealthHost,
+        username: healthUsername.trim(),
+        password: healthPassword,
+        synapseContainer: healthSynapseContainer.trim(),
+        postgresContainer: healthPostgresContainer.trim(),
+        postgresUser: healthPostgresUser.trim(),
+        postgresDatabase: healthPostgresDatabase.trim(),
+        homeserverUrl: matrixBaseUrl ?? undefined,
+        accessToken: matrixAccessToken ?? undefined
+      });
+      // The password now lives in the OS keychain; don't keep it in the form.
+      setHealthPassword("");
+      setHealthProfileSaved(true);
+      setHealthConfigRevision((revision) => revision + 1);
+    } catch (error) {
+      setHealthError((error as Error).message);
+    } finally {
+      setHealthSaving(false);
+    }
   }, [
     healthPassword,
     healthPostgresContainer,
     healthPostgresDatabase,
     healthPostgresUser,
-    healthUseMatrixHost,
     healthSynapseContainer,
     healthUsername,
-    matrixHost,
-    resolvedHealthHost
+    matrixAccessToken,
+    matrixBaseUrl,
+    resolvedHealthHost,
+    space.id,
+    space.name
   ]);
 
   const refreshServerHealth = useCallback(async () => {
-    const config = healthConfigRef.current;
-    if (!config.host.trim() || !config.username.trim()) {
-      setHealthError("Host and SSH username are required to load server health.");
+    if (!healthProfileSaved) {
+      setHealthError("Save the connection settings to load server health.");
       return;
     }
     if (healthRequestInFlightRef.current) return;
@@ -494,15 +523,7 @@ export const ServerSettingsModal = ({
     setHealthLoading(true);
     setHealthError(null);
     try {
-      const snapshot = await fetchServerHealthSnapshot({
-        host: config.host.trim(),
-        username: config.username.trim(),
-        password: config.password,
-        synapseContainer: config.synapseContainer.trim(),
-        postgresContainer: config.postgresContainer.trim(),
-        postgresUser: config.postgresUser.trim(),
-        postgresDatabase: config.postgresDatabase.trim()
-      });
+      const snapshot = await fetchServerHealthSnapshot(space.id);
       setHealthSnapshot(snapshot);
     } catch (error) {
       setHealthError((error as Error).message);
@@ -510,21 +531,20 @@ export const ServerSettingsModal = ({
       healthRequestInFlightRef.current = false;
       setHealthLoading(false);
     }
-  }, []);
+  }, [healthProfileSaved, space.id]);
 
   useEffect(() => {
     if (activeTab !== "health") return;
     if (!canViewInfrastructureHealth) return;
-    const config = healthConfigRef.current;
-    if (!config.host.trim() || !config.username.trim()) return;
+    if (!healthProfileSaved) return;
     void refreshServerHealth();
-  }, [activeTab, canViewInfrastructureHealth, healthConfigRevision, refreshServerHealth]);
+  }, [activeTab, canViewInfrastructureHealth, healthConfigRevision, healthProfileSaved, refreshServerHealth]);
 
   useEffect(() => {
     if (activeTab !== "health") return;
     if (!canViewInfrastructureHealth) return;
     if (!healthAutoRefresh) return;
-    if (!resolvedHealthHost || !healthUsername.trim()) return;
+    if (!healthProfileSaved) return;
     const interval = window.setInterval(() => {
       void refreshServerHealth();
     }, 10000);
@@ -533,8 +553,7 @@ export const ServerSettingsModal = ({
     activeTab,
     canViewInfrastructureHealth,
     healthAutoRefresh,
-    healthUsername,
-    resolvedHealthHost,
+    healthProfileSaved,
     refreshServerHealth
   ]);
 
@@ -1404,8 +1423,8 @@ export const ServerSettingsModal = ({
             <section className="settings-panel">
               <h3>Infrastructure Health</h3>
               <p>
-                Monitor live host, Matrix, and PostgreSQL metrics over SSH. Credentials are
-                saved locally on this device for this server.
+                Monitor live host, Matrix, and PostgreSQL metrics over SSH. Connection settings
+                are saved on this device; the SSH password is kept in the OS keychain.
               </p>
 
               <div className="settings-grid">
@@ -1430,7 +1449,7 @@ export const ServerSettingsModal = ({
                   SSH Password (Optional)
                   <input
                     type="password"
-                    placeholder="Use empty when SSH keys are configured"
+                    placeholder="Leave empty to keep the saved password or use SSH keys"
                     value={healthPassword}
                     onChange={(event) => setHealthPassword(event.target.value)}
                   />
@@ -1485,7 +1504,14 @@ export const ServerSettingsModal = ({
               </div>
 
               <div className="settings-row">
-                <button className="primary" onClick={() => void refreshServerHealth()} disabled={healthLoading}>
+                <button onClick={() => void saveHealthConnection()} disabled={healthSaving}>
+                  {healthSaving ? "Saving..." : "Save Connection"}
+                </button>
+                <button
+                  className="primary"
+                  onClick={() => void refreshServerHealth()}
+                  disabled={healthLoading || !healthProfileSaved}
+                >
                   {healthLoading ? "Refreshing..." : "Refresh Health"}
                 </button>
                 <label className="settings-checkbox-row">
@@ -837,6 +837,8 @@ export interface SynapseDeleteRequest {
   accessToken: string;
   roomId: string;
   requesterUserId: string;
+  /** Saved server profile; the native command reads its token from the keychain. */
+  profileId?: string;
 }
 
 export interface SynapseDeleteStatus {
@@ -984,25 +986,39 @@ export const waitForSynapseRoomRemoval = async ({
   throw new Error("Synapse still reports this room after deletion. Purge did not complete.");
 };
 
+/**
+ * The native admin commands report a profile that is missing or lacks a
+ * homeserver URL or token as `not-found` without an HTTP status.
+ */
+const isMissingProfileError = (error: unknown) => {
+  const commandError = error as { kind?: string; status?: number } | null;
+  return commandError?.kind === "not-found" && commandError.status === undefined;
+};
+
 export const requestSynapseHardDelete = async ({
   baseUrl,
   accessToken,
   roomId,
-  requesterUserId
+  requesterUserId,
+  profileId
 }: SynapseDeleteRequest) => {
   const normalizedBase = normalizeBaseUrl(baseUrl);
-  if (hasTauriRuntime()) {
+  if (profileId && hasTauriRuntime()) {
     try {
       await invoke("synapse_hard_delete_room", {
-        baseUrl: normalizedBase,
-        accessToken,
+        profileId,
         roomId,
         requesterUserId
       });
       return;
     } catch (error) {
-      // Fallback keeps browser-mode compatibility if native invoke is unavailable.
-      console.warn("Native Synapse hard-delete failed; falling back to fetch", error);
+      // Only a missing profile or credentials falls back to fetch. Any other
+      // error may come after Synapse accepted the delete, so resending it
+      // would be wrong.
+      if (!isMissingProfileError(error)) {
+        throw new Error((error as { message?: string }).message ?? String(error));
+      }
+      console.warn("No saved admin profile for Synapse hard-delete; falling back to fetch", error);
     }
   }
 
@@ -584,7 +584,9 @@ export const createRoomsSliceState = (
           baseUrl: session.baseUrl,
           accessToken: session.accessToken,
           roomId,
-          requesterUserId: state.me.id
+          requesterUserId: state.me.id,
+          // Server settings save the admin profile under the space id.
+          profileId: spaceId
         });
       } catch (error) {
         get().pushNotification(
@@ -0,0 +1,105 @@
+import { Channel, invoke } from "@tauri-apps/api/core";
+
+export type ContainerLogLevel = "debug" | "info" | "warning" | "error" | "critical";
+
+export type ContainerLogMessage =
+  | { kind: "line"; line: string; level?: ContainerLogLevel }
+  | { kind: "ended"; stopped: boolean }
+  | { kind: "failed"; error: { kind: string; message: string } };
+
+export interface ContainerLogQuery {
+  profileId: string;
+  container: string;
+  tail?: number;
+  minLevel?: ContainerLogLevel;
+}
+
+export interface ContainerLogStream {
+  streamId: string;
+  stop: () => Promise<boolean>;
+}
+
+/** Follows `docker logs` for a container of a saved server profile. */
+export const streamContainerLogs = async (
+  query: ContainerLogQuery,
+  onMessage: (message: ContainerLogMessage) => void
+): Promise<ContainerLogStream> => {
+  const channel = new Channel<ContainerLogMessage>();
+  channel.onmessage = onMessage;
+  const streamId = await invoke<string>("stream_container_logs", {
+    profileId: query.profileId,
+    container: query.container,
+    tail: query.tail ?? null,
+    minLevel: query.minLevel ?? null,
+    onMessage: channel
+  });
+  return {
+    streamId,
+    stop: () => invoke<boolean>("stop_container_logs", { streamId })
+  };
+};
+
+export type ContainerAction = "restart" | "stop" | "start" | "compose_update";
+
+export interface PendingContainerAction {
+  token: string;
+  profile_id: string;
+  profile_name: string;
+  host: string;
+  action: ContainerAction;
+  target: string;
+  command: string;
+  expires_at: number;
+}
+
+export interface ContainerActionResult {
+  action: ContainerAction;
+  target: string;
+  ok: boolean;
+  exit_status: number;
+  stdout: string;
+  stderr: string;
+  started_at: number;
+  finished_at: number;
+}
+
+export interface ContainerActionAuditEntry {
+  profile_id: string;
+  profile_name: string;
+  host: string;
+  action: ContainerAction;
+  target: string;
+  command: string;
+  started_at: number;
+  finished_at: number;
+  ok: boolean;
+  exit_status?: number;
+  error?: string;
+}
+
+/** First step: returns the exact command and a single-use confirmation token. */
+export const prepareContainerAction = (
+  profileId: string,
+  action: ContainerAction,
+  container?: string
+): Promise<PendingContainerAction> =>
+  invoke<PendingContainerAction>("prepare_container_action", {
+    profileId,
+    action,
+    container: container ?? null
+  });
+
+export const runContainerAction = (token: string): Promise<ContainerActionResult> =>
+  invoke<ContainerActionResult>("run_container_action", { token });
+
+export const cancelContainerAction = (token: string): Promise<boolean> =>
+  invoke<boolean>("cancel_container_action", { token });
+
+export const listContainerActionAudit = (
+  profileId?: string,
+  limit?: number
+): Promise<ContainerActionAuditEntry[]> =>
+  invoke<ContainerActionAuditEntry[]>("list_container_action_audit", {
+    profileId: profileId ?? null,
+    limit: limit ?? null
+  });
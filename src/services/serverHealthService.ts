@@ -1,13 +1,44 @@
 import { invoke } from "@tauri-apps/api/core";
 
 export interface ServerHealthQuery {
+  /** Saved profile to create or update; secrets are kept in the OS keychain. */
+  profileId: string;
+  profileName?: string;
   host: string;
   username: string;
   password?: string;
+  port?: number;
+  privateKeyPath?: string;
+  keyPassphrase?: string;
   synapseContainer?: string;
   postgresContainer?: string;
   postgresUser?: string;
   postgresDatabase?: string;
+  composeProjectDir?: string;
+  /** Homeserver the Synapse admin commands for this profile talk to. */
+  homeserverUrl?: string;
+  /** Admin access token; stored in the OS keychain, never in the profile. */
+  accessToken?: string;
+}
+
+export interface ServerProfile {
+  id: string;
+  name: string;
+  host: string;
+  port: number;
+  username: string;
+  private_key_path?: string | null;
+  synapse_container: string;
+  postgres_container: string;
+  postgres_user: string;
+  postgres_db: string;
+  compose_project_dir?: string | null;
+  homeserver_url?: string | null;
+  has_password: boolean;
+  has_key_passphrase: boolean;
+  has_access_token: boolean;
+  created_at: number;
+  updated_at: number;
 }
 
 export interface ServerHealthContainer {
@@ -63,22 +94,170 @@ const hasTauriRuntime = () => {
   return typeof (window as { __TAURI_INTERNALS__?: unknown }).__TAURI_INTERNALS__ !== "undefined";
 };
 
-export const fetchServerHealthSnapshot = async (
-  query: ServerHealthQuery
-): Promise<ServerHealthSnapshot> => {
+export const listServerProfiles = async (): Promise<ServerProfile[]> => {
+  if (!hasTauriRuntime()) return [];
+  return invoke<ServerProfile[]>("list_server_profiles");
+};
+
+export const deleteServerProfile = async (profileId: string): Promise<boolean> => {
+  if (!hasTauriRuntime()) return false;
+  return invoke<boolean>("delete_server_profile", { id: profileId });
+};
+
+/**
+ * Creates or updates a saved profile. Port, key path, compose dir and
+ * homeserver URL are only sent when given, so a partial form never clears
+ * them. Empty password/passphrase/access token fields leave the stored
+ * keychain secret untouched.
+ */
+export const saveServerProfile = async (query: ServerHealthQuery): Promise<ServerProfile> => {
+  const profile: Record<string, unknown> = {
+    id: query.profileId,
+    name: query.profileName?.trim() || null,
+    host: query.host,
+    username: query.username,
+    synapse_container: query.synapseContainer?.trim() || null,
+    postgres_container: query.postgresContainer?.trim() || null,
+    postgres_user: query.postgresUser?.trim() || null,
+    postgres_db: query.postgresDatabase?.trim() || null
+  };
+  // Omitted fields keep their saved value; an empty string clears them.
+  if (query.port !== undefined) profile.port = query.port;
+  if (query.privateKeyPath !== undefined) profile.private_key_path = query.privateKeyPath.trim();
+  if (query.composeProjectDir !== undefined) profile.compose_project_dir = query.composeProjectDir.trim();
+  if (query.homeserverUrl !== undefined) profile.homeserver_url = query.homeserverUrl.trim();
+  if (query.password?.trim()) profile.password = query.password;
+  if (query.keyPassphrase) profile.key_passphrase = query.keyPassphrase;
+  if (query.accessToken?.trim()) profile.access_token = query.accessToken;
+  return invoke<ServerProfile>("save_server_profile", { profile });
+};
+
+/**
+ * Collects a snapshot for a saved profile. Connection settings and secrets are
+ * read on the Rust side, so save the profile first with `saveServerProfile`.
+ */
+export const fetchServerHealthSnapshot = async (profileId: string): Promise<ServerHealthSnapshot> => {
   if (!hasTauriRuntime()) {
     throw new Error("Server health monitoring is available in the desktop app only.");
   }
+  return invoke<ServerHealthSnapshot>("fetch_remote_server_health", { profileId });
+};
 
-  const response = await invoke<ServerHealthSnapshot>("fetch_remote_server_health", {
-    host: query.host,
-    username: query.username,
-    password: query.password?.trim() ? query.password : null,
-    synapseContainer: query.synapseContainer?.trim() || null,
-    postgresContainer: query.postgresContainer?.trim() || null,
-    postgresUser: query.postgresUser?.trim() || null,
-    postgresDb: query.postgresDatabase?.trim() || null
+export type HealthMetric =
+  | "cpu_percent"
+  | "memory_used_bytes"
+  | "memory_percent"
+  | "disk_used_bytes"
+  | "disk_percent"
+  | "db_size_bytes"
+  | "db_connections";
+
+export interface HealthSeriesPoint {
+  bucket_start: number;
+  avg: number;
+  min: number;
+  max: number;
+  samples: number;
+}
+
+export interface HealthSeries {
+  metric: HealthMetric;
+  from: number;
+  to: number;
+  bucket_ms: number;
+  points: HealthSeriesPoint[];
+  first?: number | null;
+  last?: number | null;
+}
+
+export interface HealthRangeQuery {
+  profileId: string;
+  from?: number;
+  to?: number;
+}
+
+export const listHealthSnapshots = async (
+  query: HealthRangeQuery & { limit?: number }
+): Promise<Array<{ captured_at: number; snapshot: ServerHealthSnapshot }>> => {
+  if (!hasTauriRuntime()) return [];
+  return invoke("list_health_snapshots", {
+    profileId: query.profileId,
+    from: query.from ?? null,
+    to: query.to ?? null,
+    limit: query.limit ?? null
+  });
+};
+
+export const fetchHealthMetricSeries = async (
+  query: HealthRangeQuery & { metric: HealthMetric; buckets?: number }
+): Promise<HealthSeries> => {
+  if (!hasTauriRuntime()) {
+    throw new Error("Server health history is available in the desktop app only.");
+  }
+  return invoke<HealthSeries>("health_metric_series", {
+    profileId: query.profileId,
+    metric: query.metric,
+    from: query.from ?? null,
+    to: query.to ?? null,
+    buckets: query.buckets ?? null
+  });
+};
+
+export interface HealthAlertRules {
+  disk_percent_above?: number | null;
+  postgres_connections_above?: number | null;
+  container_not_running?: boolean;
+  container_unhealthy?: boolean;
+  synapse_version_changed?: boolean;
+}
+
+export interface HealthMonitor {
+  profile_id: string;
+  enabled: boolean;
+  interval_secs: number;
+  cooldown_secs: number;
+  rules: Required<HealthAlertRules>;
+  last_synapse_version?: string | null;
+}
+
+/** Payload of the `health-alert` app event. */
+export interface HealthAlert {
+  profile_id: string;
+  profile_name: string;
+  rule:
+    | "disk_usage"
+    | "postgres_connections"
+    | "container_not_running"
+    | "container_unhealthy"
+    | "synapse_version_changed";
+  subject?: string;
+  message: string;
+  raised_at: number;
+}
+
+export const listHealthMonitors = async (): Promise<HealthMonitor[]> => {
+  if (!hasTauriRuntime()) return [];
+  return invoke<HealthMonitor[]>("list_health_monitors");
+};
+
+export const setHealthMonitor = async (monitor: {
+  profileId: string;
+  enabled?: boolean;
+  intervalSecs?: number;
+  cooldownSecs?: number;
+  rules?: HealthAlertRules;
+}): Promise<HealthMonitor> =>
+  invoke<HealthMonitor>("set_health_monitor", {
+    monitor: {
+      profile_id: monitor.profileId,
+      enabled: monitor.enabled ?? true,
+      interval_secs: monitor.intervalSecs ?? null,
+      cooldown_secs: monitor.cooldownSecs ?? null,
+      rules: monitor.rules ?? null
+    }
   });
 
-  return response;
+export const removeHealthMonitor = async (profileId: string): Promise<boolean> => {
+  if (!hasTauriRuntime()) return false;
+  return invoke<boolean>("remove_health_monitor", { profileId });
 };